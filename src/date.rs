@@ -0,0 +1,367 @@
+#[allow(unused)]
+use {
+    super::error::MemoError,
+    chrono::{Duration, Local, Months, NaiveDate, NaiveDateTime, NaiveTime},
+    error_stack::{Report, Result, ResultExt},
+    jlogger_tracing::{
+        jdebug, jerror, jinfo, jtrace, jwarn, JloggerBuilder, LevelFilter, LogTimeFormat,
+    },
+    std::fmt::{self, Debug, Display},
+};
+
+/// A half-open time range `[start, end)` used to filter notes by creation time.
+///
+/// A missing bound means the range is open on that side.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct DateRange {
+    start: Option<NaiveDateTime>,
+    end: Option<NaiveDateTime>,
+}
+
+impl DateRange {
+    fn invalid(s: &str) -> Report<MemoError> {
+        Report::new(MemoError::InvalidValue).attach_printable(format!(
+            "Invalid date `{s}`, use like 2026-01-31, 2026-01, 2026-01-31T10:30, today, yesterday or last-7d"
+        ))
+    }
+
+    /// Parse a point in time into the range of time it covers, e.g. `2026-01` covers
+    /// the whole month and `last-7d` covers the last 7 days till now.
+    fn parse_point(s: &str) -> Result<Self, MemoError> {
+        let now = Local::now().naive_local();
+        let today = now.date().and_time(NaiveTime::MIN);
+
+        match s {
+            "today" => {
+                return Ok(Self {
+                    start: Some(today),
+                    end: Some(today + Duration::days(1)),
+                })
+            }
+            "yesterday" => {
+                return Ok(Self {
+                    start: Some(today - Duration::days(1)),
+                    end: Some(today),
+                })
+            }
+            _ => {}
+        }
+
+        if let Some(last) = s.strip_prefix("last-") {
+            if last.len() < 2 || !last.is_char_boundary(last.len() - 1) {
+                return Err(DateRange::invalid(s));
+            }
+
+            let (n, unit) = last.split_at(last.len() - 1);
+            let n = n
+                .parse::<i64>()
+                .ok()
+                .filter(|n| *n >= 0)
+                .ok_or_else(|| DateRange::invalid(s))?;
+            let back = |d: Option<Duration>| {
+                d.and_then(|d| now.checked_sub_signed(d))
+                    .ok_or_else(|| DateRange::invalid(s))
+            };
+            let start = match unit {
+                "h" => back(Duration::try_hours(n))?,
+                "d" => back(Duration::try_days(n))?,
+                "w" => back(Duration::try_weeks(n))?,
+                "m" | "y" => u32::try_from(n)
+                    .ok()
+                    .and_then(|n| {
+                        if unit == "y" {
+                            n.checked_mul(12)
+                        } else {
+                            Some(n)
+                        }
+                    })
+                    .and_then(|n| now.checked_sub_months(Months::new(n)))
+                    .ok_or_else(|| DateRange::invalid(s))?,
+                _ => return Err(DateRange::invalid(s)),
+            };
+
+            return Ok(Self {
+                start: Some(start),
+                end: None,
+            });
+        }
+
+        if let Ok(t) = NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S") {
+            return Ok(Self {
+                start: Some(t),
+                end: Some(t + Duration::seconds(1)),
+            });
+        }
+
+        if let Ok(t) = NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M") {
+            return Ok(Self {
+                start: Some(t),
+                end: Some(t + Duration::minutes(1)),
+            });
+        }
+
+        if let Ok(d) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+            let t = d.and_time(NaiveTime::MIN);
+            return Ok(Self {
+                start: Some(t),
+                end: Some(t + Duration::days(1)),
+            });
+        }
+
+        if let Ok(d) = NaiveDate::parse_from_str(&format!("{s}-01"), "%Y-%m-%d") {
+            let t = d.and_time(NaiveTime::MIN);
+            return Ok(Self {
+                start: Some(t),
+                end: t.checked_add_months(Months::new(1)),
+            });
+        }
+
+        if s.len() == 4 {
+            if let Ok(d) = NaiveDate::parse_from_str(&format!("{s}-01-01"), "%Y-%m-%d") {
+                let t = d.and_time(NaiveTime::MIN);
+                return Ok(Self {
+                    start: Some(t),
+                    end: t.checked_add_months(Months::new(12)),
+                });
+            }
+        }
+
+        Err(DateRange::invalid(s))
+    }
+
+    /// Parse a date expression.
+    ///
+    /// Supported forms are a point in time (`2026-01-31`), a comparison (`>=2026-01-01`,
+    /// `<2026-02`) and a range (`2026-01-01..2026-01-31`, both ends inclusive).
+    pub fn parse(s: &str) -> Result<Self, MemoError> {
+        let s = s.trim();
+
+        if let Some((from, to)) = s.split_once("..") {
+            let start = if from.is_empty() {
+                None
+            } else {
+                DateRange::parse_point(from)?.start
+            };
+
+            let end = if to.is_empty() {
+                None
+            } else {
+                DateRange::parse_point(to)?.end
+            };
+
+            return Ok(Self { start, end });
+        }
+
+        let range = if let Some(p) = s.strip_prefix(">=") {
+            let r = DateRange::parse_point(p)?;
+            Self {
+                start: r.start,
+                end: None,
+            }
+        } else if let Some(p) = s.strip_prefix("<=") {
+            let r = DateRange::parse_point(p)?;
+            Self {
+                start: None,
+                end: r.end,
+            }
+        } else if let Some(p) = s.strip_prefix('>') {
+            let r = DateRange::parse_point(p)?;
+            Self {
+                start: r.end,
+                end: None,
+            }
+        } else if let Some(p) = s.strip_prefix('<') {
+            let r = DateRange::parse_point(p)?;
+            Self {
+                start: None,
+                end: r.start,
+            }
+        } else {
+            DateRange::parse_point(s.strip_prefix('=').unwrap_or(s))?
+        };
+
+        jdebug!("date `{s}` => {:?}", range);
+        Ok(range)
+    }
+
+    /// Range starting from the beginning of `s`.
+    pub fn since(s: &str) -> Result<Self, MemoError> {
+        Ok(Self {
+            start: DateRange::parse(s)?.start,
+            end: None,
+        })
+    }
+
+    /// Range ending at the end of `s`.
+    pub fn until(s: &str) -> Result<Self, MemoError> {
+        Ok(Self {
+            start: None,
+            end: DateRange::parse(s)?.end,
+        })
+    }
+
+    /// Intersection of two ranges.
+    pub fn and(self, other: Self) -> Self {
+        let start = match (self.start, other.start) {
+            (Some(a), Some(b)) => Some(a.max(b)),
+            (a, b) => a.or(b),
+        };
+
+        let end = match (self.end, other.end) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+
+        Self { start, end }
+    }
+
+    pub fn contains(&self, t: NaiveDateTime) -> bool {
+        self.start.map(|s| t >= s).unwrap_or(true) && self.end.map(|e| t < e).unwrap_or(true)
+    }
+}
+
+impl Display for DateRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let fmt = |t: Option<NaiveDateTime>| {
+            t.map(|t| t.format("%Y/%m/%d %H:%M:%S").to_string())
+                .unwrap_or_default()
+        };
+
+        write!(f, "{}..{}", fmt(self.start), fmt(self.end))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn t(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S").unwrap()
+    }
+
+    fn range(start: Option<&str>, end: Option<&str>) -> DateRange {
+        DateRange {
+            start: start.map(t),
+            end: end.map(t),
+        }
+    }
+
+    #[test]
+    fn points() {
+        assert_eq!(
+            DateRange::parse("2026-01-31").unwrap(),
+            range(Some("2026-01-31T00:00:00"), Some("2026-02-01T00:00:00"))
+        );
+        assert_eq!(
+            DateRange::parse("2026-12").unwrap(),
+            range(Some("2026-12-01T00:00:00"), Some("2027-01-01T00:00:00"))
+        );
+        assert_eq!(
+            DateRange::parse("2026").unwrap(),
+            range(Some("2026-01-01T00:00:00"), Some("2027-01-01T00:00:00"))
+        );
+        assert_eq!(
+            DateRange::parse("2026-01-31T10:30").unwrap(),
+            range(Some("2026-01-31T10:30:00"), Some("2026-01-31T10:31:00"))
+        );
+        assert_eq!(
+            DateRange::parse("=2026-01-31T10:30:15").unwrap(),
+            range(Some("2026-01-31T10:30:15"), Some("2026-01-31T10:30:16"))
+        );
+    }
+
+    #[test]
+    fn relative_points() {
+        let today = Local::now().date_naive().and_time(NaiveTime::MIN);
+        let day = Duration::days(1);
+        assert_eq!(
+            DateRange::parse("today").unwrap(),
+            DateRange {
+                start: Some(today),
+                end: Some(today + day),
+            }
+        );
+        assert_eq!(
+            DateRange::parse("yesterday").unwrap(),
+            DateRange {
+                start: Some(today - day),
+                end: Some(today),
+            }
+        );
+
+        let week = DateRange::parse("last-7d").unwrap();
+        assert!(week.end.is_none());
+        assert!(week.contains(Local::now().naive_local() - Duration::days(6)));
+        assert!(!week.contains(Local::now().naive_local() - Duration::days(8)));
+        for s in ["last-0h", "last-12h", "last-2w", "last-3m", "last-1y"] {
+            assert!(DateRange::parse(s).is_ok(), "`{s}` should be accepted");
+        }
+    }
+
+    #[test]
+    fn comparisons_and_ranges() {
+        assert_eq!(
+            DateRange::parse(">=2026-01").unwrap(),
+            range(Some("2026-01-01T00:00:00"), None)
+        );
+        assert_eq!(
+            DateRange::parse(">2026-01").unwrap(),
+            range(Some("2026-02-01T00:00:00"), None)
+        );
+        assert_eq!(
+            DateRange::parse("<=2026-01-31").unwrap(),
+            range(None, Some("2026-02-01T00:00:00"))
+        );
+        assert_eq!(
+            DateRange::parse("<2026-01-31").unwrap(),
+            range(None, Some("2026-01-31T00:00:00"))
+        );
+        assert_eq!(
+            DateRange::parse("2026-01-01..2026-01-31").unwrap(),
+            range(Some("2026-01-01T00:00:00"), Some("2026-02-01T00:00:00"))
+        );
+        assert_eq!(
+            DateRange::parse("2026-01..").unwrap(),
+            range(Some("2026-01-01T00:00:00"), None)
+        );
+        assert_eq!(
+            DateRange::parse("..2026").unwrap(),
+            range(None, Some("2027-01-01T00:00:00"))
+        );
+    }
+
+    #[test]
+    fn contains_is_half_open() {
+        let day = DateRange::parse("2026-01-31").unwrap();
+        assert!(day.contains(t("2026-01-31T00:00:00")));
+        assert!(day.contains(t("2026-01-31T23:59:59")));
+        assert!(!day.contains(t("2026-02-01T00:00:00")));
+        assert!(!day.contains(t("2026-01-30T23:59:59")));
+    }
+
+    #[test]
+    fn invalid() {
+        for s in [
+            "",
+            "last-",
+            "last-d",
+            "last-7",
+            "last-7x",
+            "last--1d",
+            "last-1é",
+            "last-99999999999999999999d",
+            "last-9999999999999999h",
+            "last-9999999999999999w",
+            "last-4294967295m",
+            "last-400000000y",
+            "2026-13",
+            "2026-02-30",
+            "26",
+            "tomorrow",
+            ">=",
+            "2026-01-01..x",
+        ] {
+            assert!(DateRange::parse(s).is_err(), "`{s}` should be rejected");
+        }
+    }
+}
@@ -0,0 +1,305 @@
+#[allow(unused)]
+use {
+    super::{
+        error::MemoError,
+        memo::{FileName, Memo, MemoEntry, KINDS},
+    },
+    error_stack::{Report, Result, ResultExt},
+    jlogger_tracing::{
+        jdebug, jerror, jinfo, jtrace, jwarn, JloggerBuilder, LevelFilter, LogTimeFormat,
+    },
+    std::{
+        fmt::{self, Display},
+        fs,
+        io::Write,
+        path::{Path, PathBuf},
+    },
+};
+
+/// Other spellings of note suffixes, renamed by "memo doctor --fix".
+const SUFFIX_ALIASES: [(&str, &str); 4] = [
+    ("text", "txt"),
+    ("htm", "html"),
+    ("markdown", "md"),
+    ("mkd", "md"),
+];
+
+/// Seconds since the last modification before a broken note is moved to the quarantine when
+/// notes are loaded, a note being written may look broken for a moment.
+const GRACE_SECS: u64 = 3600;
+
+/// What is wrong with a file of the store.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Problem {
+    /// Not a note, like an editor swap file, it is skipped when notes are loaded.
+    Unrecognized,
+    /// A note which can not be loaded, it is moved to the quarantine once it is not modified
+    /// for a while.
+    Broken,
+    /// A broken note in the quarantine.
+    Quarantined,
+}
+
+/// How a problem is repaired.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Fix {
+    /// Rename to the note file name with the right suffix.
+    Rename(String),
+    /// Move to the quarantine.
+    Quarantine,
+    /// Move back to the notes, the note is loaded fine now.
+    Restore,
+}
+
+/// A file of the store with a problem.
+pub struct Finding {
+    pub path: String,
+    pub problem: Problem,
+    pub reason: String,
+    pub fix: Option<Fix>,
+}
+
+impl Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Problem::Unrecognized => "unrecognized",
+            Problem::Broken => "broken",
+            Problem::Quarantined => "quarantined",
+        };
+
+        write!(f, "{s}")
+    }
+}
+
+impl Display for Fix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Fix::Rename(to) => write!(f, "rename to {to}"),
+            Fix::Quarantine => write!(f, "move to the quarantine"),
+            Fix::Restore => write!(f, "restore"),
+        }
+    }
+}
+
+pub struct Doctor;
+
+impl Doctor {
+    fn quarantine_dir(root: &str) -> String {
+        format!("{root}/quarantine")
+    }
+
+    fn file_name(path: &Path) -> &str {
+        path.file_name().and_then(|a| a.to_str()).unwrap_or("")
+    }
+
+    /// Hidden files like ".note.txt.swp" of vim.
+    pub fn is_hidden(path: &Path) -> bool {
+        Doctor::file_name(path).starts_with('.')
+    }
+
+    /// Why `path` is not a note file, `None` if it is one.
+    pub fn unrecognized(path: &Path) -> Option<String> {
+        let name = Doctor::file_name(path);
+        if Doctor::is_hidden(path) {
+            return Some("hidden file".to_owned());
+        }
+
+        match path.extension().and_then(|a| a.to_str()) {
+            Some(s) if !KINDS.contains(&s) => return Some(format!("unknown suffix `{s}`")),
+            None => return Some("no suffix".to_owned()),
+            _ => {}
+        }
+
+        match FileName::from_file_name(name) {
+            Ok(n) if n.datetime().is_some() => None,
+            _ => Some("not a note file name".to_owned()),
+        }
+    }
+
+    /// Whether `path` is not modified for the grace period, and can be moved to the
+    /// quarantine by any command if it is broken.
+    pub fn settled(path: &Path) -> bool {
+        fs::metadata(path)
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|t| t.elapsed().ok())
+            .is_some_and(|d| d.as_secs() >= GRACE_SECS)
+    }
+
+    /// Warn about the files skipped when notes are loaded.
+    ///
+    /// The list is remembered in the index directory and the warning is given again only when
+    /// it changes, so that it is not repeated by every command.
+    pub fn report_skipped(root: &str, memo_dir: &str, mut skipped: Vec<String>) {
+        let record = format!("{root}/index/skipped");
+        skipped.sort();
+        let list: String = skipped.iter().map(|f| format!("{f}\n")).collect();
+        if fs::read_to_string(&record).is_ok_and(|a| a == list) {
+            return;
+        }
+
+        if !skipped.is_empty() {
+            jwarn!(
+                "{} files in {memo_dir} are skipped, see \"memo doctor\".",
+                skipped.len()
+            );
+        }
+
+        let saved =
+            fs::create_dir_all(format!("{root}/index")).and_then(|_| fs::write(&record, list));
+        if let Err(e) = saved {
+            jdebug!("Failed to write {record}: {e}");
+        }
+    }
+
+    /// Move a broken note `file` to the quarantine, returns the new path.
+    ///
+    /// A file already in the quarantine is never overwritten.
+    pub fn quarantine(root: &str, file: &str) -> Result<String, MemoError> {
+        let dir = Doctor::quarantine_dir(root);
+        let to = format!("{dir}/{}", Doctor::file_name(Path::new(file)));
+        Doctor::move_file(file, &to)?;
+        Ok(to)
+    }
+
+    /// Rename `from` to `to` unless `to` exists.
+    fn move_file(from: &str, to: &str) -> Result<(), MemoError> {
+        if Path::new(to).exists() {
+            return Err(Report::new(MemoError::InvalidValue))
+                .attach_printable(format!("{to} already exists."));
+        }
+
+        if let Some(dir) = Path::new(to).parent() {
+            fs::create_dir_all(dir).map_err(|e| {
+                Report::new(MemoError::IOError)
+                    .attach_printable(format!("Failed to create {}: {e}", dir.display()))
+            })?;
+        }
+
+        fs::rename(from, to).map_err(|e| {
+            Report::new(MemoError::IOError)
+                .attach_printable(format!("Failed to move {from} to {to}: {e}"))
+        })
+    }
+
+    /// Files in `dir` in name order.
+    fn files(dir: &str) -> Vec<PathBuf> {
+        let mut files: Vec<PathBuf> = fs::read_dir(dir)
+            .map(|d| {
+                d.flatten()
+                    .map(|e| e.path())
+                    .filter(|p| p.is_file())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        files.sort();
+        files
+    }
+
+    /// Note file name for an unrecognized file like "2024_01_02_03_04_05.markdown".
+    fn renamed(path: &Path) -> Option<String> {
+        let suffix = path.extension()?.to_str()?.to_lowercase();
+        let (_, kind) = SUFFIX_ALIASES.iter().find(|(a, _)| *a == suffix)?;
+        let stem = path.file_stem()?.to_str()?;
+        let name = format!("{stem}.{kind}");
+
+        FileName::from_file_name(&name)
+            .ok()
+            .filter(|n| n.datetime().is_some())
+            .map(|_| name)
+    }
+
+    /// Short reason why a note can not be loaded.
+    pub fn reason(e: &Report<MemoError>) -> String {
+        e.frames()
+            .find_map(|f| f.downcast_ref::<String>())
+            .cloned()
+            .unwrap_or_else(|| e.current_context().to_string())
+    }
+
+    /// Check the notes and the quarantine under `root`.
+    pub fn inspect(root: &str) -> Vec<Finding> {
+        let memo_dir = format!("{root}/memo");
+        let mut findings = vec![];
+
+        for path in Doctor::files(&memo_dir) {
+            let file = path.to_string_lossy().into_owned();
+            if let Some(reason) = Doctor::unrecognized(&path) {
+                findings.push(Finding {
+                    fix: Doctor::renamed(&path)
+                        .filter(|n| !Path::new(&format!("{memo_dir}/{n}")).exists())
+                        .map(Fix::Rename),
+                    path: file,
+                    problem: Problem::Unrecognized,
+                    reason,
+                });
+                continue;
+            }
+
+            if let Err(e) = MemoEntry::load(&file) {
+                if matches!(e.current_context(), MemoError::BrokenNote) {
+                    findings.push(Finding {
+                        path: file,
+                        problem: Problem::Broken,
+                        reason: Doctor::reason(&e),
+                        fix: Some(Fix::Quarantine),
+                    });
+                }
+            }
+        }
+
+        for path in Doctor::files(&Doctor::quarantine_dir(root)) {
+            let file = path.to_string_lossy().into_owned();
+            let (reason, fix) = match MemoEntry::load(&file) {
+                Ok(_) => ("loaded fine now".to_owned(), Some(Fix::Restore)),
+                Err(e) => {
+                    let utf8 = fs::read(&file)
+                        .map(|b| String::from_utf8(b).is_ok())
+                        .unwrap_or(true);
+                    // The encoding can not be told reliably, so it is left to the user.
+                    let hint = if utf8 {
+                        ""
+                    } else {
+                        ", convert it to UTF-8 like \"iconv -f SHIFT_JIS -t UTF-8\" and run \"memo doctor --fix\""
+                    };
+                    (format!("{}{hint}", Doctor::reason(&e)), None)
+                }
+            };
+
+            let taken = Path::new(&format!("{memo_dir}/{}", Doctor::file_name(&path))).exists();
+            findings.push(Finding {
+                path: file,
+                problem: Problem::Quarantined,
+                reason,
+                fix: fix.filter(|_| !taken),
+            });
+        }
+
+        findings
+    }
+}
+
+impl Finding {
+    /// Apply the fix of the finding, returns the new path of the file.
+    pub fn repair(&self, root: &str) -> Result<String, MemoError> {
+        let memo_dir = format!("{root}/memo");
+        let name = Doctor::file_name(Path::new(&self.path)).to_owned();
+
+        match &self.fix {
+            None => Err(Report::new(MemoError::InvalidValue))
+                .attach_printable(format!("No fix for {}.", self.path)),
+            Some(Fix::Rename(to)) => {
+                let to = format!("{memo_dir}/{to}");
+                Doctor::move_file(&self.path, &to)?;
+                Ok(to)
+            }
+            Some(Fix::Quarantine) => Doctor::quarantine(root, &self.path),
+            Some(Fix::Restore) => {
+                let to = format!("{memo_dir}/{name}");
+                Doctor::move_file(&self.path, &to)?;
+                Ok(to)
+            }
+        }
+    }
+}
@@ -19,6 +19,8 @@ use {
 pub enum MemoError {
     InvalidValue,
     IOError,
+    /// A note file which can not be read as a note, like an empty one.
+    BrokenNote,
     Unexpected,
 }
 
@@ -27,6 +29,7 @@ impl Display for MemoError {
         let msg = match self {
             MemoError::InvalidValue => "Invalid value",
             MemoError::IOError => "IO error",
+            MemoError::BrokenNote => "Broken note",
             MemoError::Unexpected => "Unexpected error",
         };
 
@@ -0,0 +1,536 @@
+#[allow(unused)]
+use {
+    chrono::{DateTime, Local, NaiveDate, NaiveDateTime, NaiveTime},
+    std::collections::BTreeMap,
+};
+
+/// Value of a front matter field.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Str(String),
+    List(Vec<String>),
+}
+
+impl Value {
+    /// Items of a list, a single value is a list of one item.
+    pub fn items(&self) -> Vec<&str> {
+        match self {
+            Value::Str(s) => vec![s.as_str()],
+            Value::List(l) => l.iter().map(|a| a.as_str()).collect(),
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::Str(s) => Some(s),
+            Value::List(_) => None,
+        }
+    }
+}
+
+/// Syntax of a front matter block.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Syntax {
+    /// Between "---" lines, `key: value`
+    Yaml,
+    /// Between "+++" lines, `key = value`
+    Toml,
+}
+
+/// Metadata block at the top of a note.
+///
+/// Only the simple part of YAML and TOML used for metadata is supported: one field per line
+/// with a string, a number, a date or a list of them. A YAML list may also be written as
+/// "- item" lines under the key.
+#[derive(Debug, Clone, Default)]
+pub struct FrontMatter {
+    fields: BTreeMap<String, Value>,
+    /// The block as it is written, with the delimiters.
+    source: String,
+}
+
+impl FrontMatter {
+    fn syntax(line: &str) -> Option<Syntax> {
+        match line.trim_end() {
+            "---" => Some(Syntax::Yaml),
+            "+++" => Some(Syntax::Toml),
+            _ => None,
+        }
+    }
+
+    /// Lines of the block between the delimiters and the offset of the text following it.
+    fn block(content: &str) -> Option<(Syntax, Vec<&str>, usize)> {
+        let first = content.split_inclusive('\n').next()?;
+        let syntax = FrontMatter::syntax(first)?;
+
+        let mut offset = first.len();
+        let mut lines = vec![];
+        for line in content[offset..].split_inclusive('\n') {
+            offset += line.len();
+            if FrontMatter::syntax(line) == Some(syntax)
+                || (syntax == Syntax::Yaml && line.trim_end() == "...")
+            {
+                return Some((syntax, lines, offset));
+            }
+            lines.push(line.trim_end_matches(['\r', '\n']));
+        }
+
+        None
+    }
+
+    /// Parse the front matter at the top of `content`, the rest of the content is returned
+    /// with it.
+    pub fn parse(content: &str) -> Option<(FrontMatter, &str)> {
+        let (syntax, lines, offset) = FrontMatter::block(content)?;
+        let mut fields = BTreeMap::new();
+        let mut list_key: Option<String> = None;
+
+        for line in lines {
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+
+            if syntax == Syntax::Yaml {
+                if let (Some(key), Some(item)) = (&list_key, trimmed.strip_prefix("- ")) {
+                    if let Some(Value::List(l)) = fields.get_mut(key) {
+                        l.push(FrontMatter::scalar(FrontMatter::strip_comment(item)));
+                    }
+                    continue;
+                }
+            }
+
+            let sep = if syntax == Syntax::Yaml { ':' } else { '=' };
+            let Some((key, value)) = line.split_once(sep) else {
+                continue;
+            };
+
+            let key = FrontMatter::scalar(key.trim()).to_lowercase();
+            let value = FrontMatter::strip_comment(value.trim());
+            list_key = None;
+
+            let value = if value.is_empty() && syntax == Syntax::Yaml {
+                list_key = Some(key.clone());
+                Value::List(vec![])
+            } else if let Some(items) = FrontMatter::list(value) {
+                Value::List(items)
+            } else {
+                Value::Str(FrontMatter::scalar(value))
+            };
+
+            fields.insert(key, value);
+        }
+
+        Some((
+            Self {
+                fields,
+                source: content[..offset].to_owned(),
+            },
+            &content[offset..],
+        ))
+    }
+
+    /// Remove a "# comment" following a value, a "#" in quotes is kept.
+    fn strip_comment(s: &str) -> &str {
+        let mut quote = None;
+        for (i, c) in s.char_indices() {
+            match (quote, c) {
+                (None, '"' | '\'') => quote = Some(c),
+                (Some(q), c) if c == q => quote = None,
+                (None, '#') if i == 0 || s[..i].ends_with(char::is_whitespace) => {
+                    return s[..i].trim_end()
+                }
+                _ => {}
+            }
+        }
+
+        s
+    }
+
+    /// A string without quotes.
+    fn scalar(s: &str) -> String {
+        let s = s.trim();
+        for q in ['"', '\''] {
+            if s.len() >= 2 && s.starts_with(q) && s.ends_with(q) {
+                let inner = &s[1..s.len() - 1];
+                return if q == '"' {
+                    inner.replace("\\\"", "\"").replace("\\\\", "\\")
+                } else {
+                    inner.replace("''", "'")
+                };
+            }
+        }
+
+        s.to_owned()
+    }
+
+    /// Items of an inline list like `[a, "b, c"]`.
+    fn list(s: &str) -> Option<Vec<String>> {
+        let inner = s.strip_prefix('[')?.strip_suffix(']')?;
+        let mut items = vec![];
+        let mut item = String::new();
+        let mut quote = None;
+
+        for c in inner.chars() {
+            match (quote, c) {
+                (None, '"' | '\'') => {
+                    quote = Some(c);
+                    item.push(c);
+                }
+                (Some(q), c) if c == q => {
+                    quote = None;
+                    item.push(c);
+                }
+                (None, ',') => items.push(FrontMatter::scalar(&std::mem::take(&mut item))),
+                _ => item.push(c),
+            }
+        }
+
+        if !item.trim().is_empty() {
+            items.push(FrontMatter::scalar(&item));
+        }
+
+        Some(items.into_iter().filter(|a| !a.is_empty()).collect())
+    }
+
+    /// Whether `line` of a front matter is the field `key`.
+    pub fn is_field(line: &str, key: &str) -> bool {
+        line.split_once([':', '='])
+            .is_some_and(|(k, _)| FrontMatter::scalar(k).eq_ignore_ascii_case(key))
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.fields.get(key)
+    }
+
+    pub fn fields(&self) -> &BTreeMap<String, Value> {
+        &self.fields
+    }
+
+    /// The block as it is written, empty if the note has no front matter.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Parse a date like "2026-01-31", "2026-01-31 10:30" or "2026-01-31T10:30:00+09:00".
+    pub fn datetime(s: &str) -> Option<NaiveDateTime> {
+        if let Ok(t) = DateTime::parse_from_rfc3339(s) {
+            return Some(t.with_timezone(&Local).naive_local());
+        }
+
+        for fmt in [
+            "%Y-%m-%dT%H:%M:%S",
+            "%Y-%m-%d %H:%M:%S",
+            "%Y-%m-%dT%H:%M",
+            "%Y-%m-%d %H:%M",
+        ] {
+            if let Ok(t) = NaiveDateTime::parse_from_str(s, fmt) {
+                return Some(t);
+            }
+        }
+
+        NaiveDate::parse_from_str(s, "%Y-%m-%d")
+            .ok()
+            .map(|d| d.and_time(NaiveTime::MIN))
+    }
+
+    /// A value written in `syntax`, quoted if needed.
+    fn quote(syntax: Syntax, s: &str) -> String {
+        if syntax == Syntax::Toml || s.contains([',', '#', '"', ':']) {
+            format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+        } else {
+            s.to_owned()
+        }
+    }
+
+    /// Set `title` and add `tags` to the front matter at the top of `content`.
+    ///
+    /// The given fields are written at the end of the block and replace the old ones, the old
+    /// tags are kept in front of the new ones. `content` is returned unchanged if it has no
+    /// front matter.
+    pub fn insert(content: &str, title: Option<&str>, tags: &[String]) -> String {
+        let Some((syntax, lines, offset)) = FrontMatter::block(content) else {
+            return content.to_owned();
+        };
+
+        let mut items: Vec<String> = FrontMatter::parse(content)
+            .and_then(|(f, _)| {
+                f.get("tags")
+                    .map(|t| t.items().iter().map(|a| a.to_string()).collect())
+            })
+            .unwrap_or_default();
+        for tag in tags {
+            if !items.contains(tag) {
+                items.push(tag.clone());
+            }
+        }
+
+        let newline = if content.contains("\r\n") {
+            "\r\n"
+        } else {
+            "\n"
+        };
+        let first = content.split_inclusive('\n').next().unwrap_or_default();
+        let last = content[..offset]
+            .trim_end_matches(['\r', '\n'])
+            .rsplit('\n')
+            .next()
+            .unwrap_or_default();
+        let sep = if syntax == Syntax::Yaml { ": " } else { " = " };
+
+        let mut out = String::from(first);
+        let mut in_tags = false;
+        for line in lines {
+            let trimmed = line.trim();
+            if in_tags && syntax == Syntax::Yaml && trimmed.starts_with("- ") {
+                continue;
+            }
+
+            in_tags = false;
+            if !tags.is_empty() && FrontMatter::is_field(line, "tags") {
+                in_tags = true;
+                continue;
+            }
+
+            if title.is_some() && FrontMatter::is_field(line, "title") {
+                continue;
+            }
+
+            out.push_str(line);
+            out.push_str(newline);
+        }
+
+        if let Some(title) = title {
+            out.push_str(&format!(
+                "title{sep}{}{newline}",
+                FrontMatter::quote(syntax, title)
+            ));
+        }
+
+        if !tags.is_empty() {
+            let items: Vec<String> = items
+                .iter()
+                .map(|a| FrontMatter::quote(syntax, a))
+                .collect();
+            out.push_str(&format!("tags{sep}[{}]{newline}", items.join(", ")));
+        }
+
+        out.push_str(last);
+        out.push_str(newline);
+        out.push_str(&content[offset..]);
+        out
+    }
+
+    /// Rewrite the items of the `tags` field in `content` by `f`, which returns the new name of
+    /// a tag or `None` to remove it. `content` is returned unchanged if it has no front matter.
+    pub fn retag<F>(content: &str, f: F) -> String
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let Some((syntax, lines, offset)) = FrontMatter::block(content) else {
+            return content.to_owned();
+        };
+
+        let newline = if content.contains("\r\n") {
+            "\r\n"
+        } else {
+            "\n"
+        };
+        let first = content.split_inclusive('\n').next().unwrap_or_default();
+        let last = content[..offset]
+            .trim_end_matches(['\r', '\n'])
+            .rsplit('\n')
+            .next()
+            .unwrap_or_default();
+
+        let rewrite = |items: Vec<String>| -> Vec<String> {
+            let mut out: Vec<String> = vec![];
+            for item in items {
+                if let Some(new) = f(&item) {
+                    if !out.contains(&new) {
+                        out.push(new);
+                    }
+                }
+            }
+            out
+        };
+
+        let quote = |s: &str| FrontMatter::quote(syntax, s);
+
+        let sep = if syntax == Syntax::Yaml { ':' } else { '=' };
+        let mut out = String::from(first);
+        let mut in_tags = false;
+        let mut seen: Vec<String> = vec![];
+
+        for line in lines {
+            let trimmed = line.trim();
+
+            if in_tags && syntax == Syntax::Yaml {
+                if let Some(raw) = trimmed.strip_prefix("- ") {
+                    let item = FrontMatter::strip_comment(raw);
+                    if let Some(new) = f(&FrontMatter::scalar(item)) {
+                        if !seen.contains(&new) {
+                            let indent = &line[..line.len() - line.trim_start().len()];
+                            let comment = &raw[item.len()..];
+                            out.push_str(&format!("{indent}- {}{comment}{newline}", quote(&new)));
+                            seen.push(new);
+                        }
+                    }
+                    continue;
+                }
+            }
+
+            in_tags = false;
+            let field = line
+                .split_once(sep)
+                .filter(|(k, _)| FrontMatter::scalar(k.trim()).eq_ignore_ascii_case("tags"));
+
+            match field {
+                Some((key, value)) => {
+                    let raw = value.trim();
+                    let value = FrontMatter::strip_comment(raw);
+                    if value.is_empty() && syntax == Syntax::Yaml {
+                        in_tags = true;
+                        out.push_str(line);
+                    } else {
+                        let items = FrontMatter::list(value)
+                            .unwrap_or_else(|| vec![FrontMatter::scalar(value)]);
+                        let items: Vec<String> = rewrite(items).iter().map(|a| quote(a)).collect();
+                        let comment = &raw[value.len()..];
+                        out.push_str(&format!("{key}{sep} [{}]{comment}", items.join(", ")));
+                    }
+                }
+                None => out.push_str(line),
+            }
+            out.push_str(newline);
+        }
+
+        out.push_str(last);
+        out.push_str(newline);
+        out.push_str(&content[offset..]);
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn str(s: &str) -> Value {
+        Value::Str(s.to_owned())
+    }
+
+    fn list(items: &[&str]) -> Value {
+        Value::List(items.iter().map(|a| a.to_string()).collect())
+    }
+
+    fn fields(content: &str) -> BTreeMap<String, Value> {
+        FrontMatter::parse(content).unwrap().0.fields
+    }
+
+    #[test]
+    fn quotes_and_comments() {
+        let f = fields(concat!(
+            "---\n",
+            "# a comment line\n",
+            "Title: \"Say \\\"hi\\\" # not a comment\"\n",
+            "status: open # a comment\n",
+            "alias: 'it''s'\n",
+            "url: http://example.com/#top\n",
+            "---\n",
+        ));
+        assert_eq!(f["title"], str("Say \"hi\" # not a comment"));
+        assert_eq!(f["status"], str("open"));
+        assert_eq!(f["alias"], str("it's"));
+        assert_eq!(f["url"], str("http://example.com/#top"));
+        assert_eq!(f.len(), 4);
+    }
+
+    #[test]
+    fn lists() {
+        let f = fields(concat!(
+            "---\n",
+            "tags: [rust, \"a, b\", 'c']\n",
+            "empty: []\n",
+            "aliases:\n",
+            "  - one\n",
+            "  - \"two: 2\" # a comment\n",
+            "status: open\n",
+            "- not an item\n",
+            "---\n",
+        ));
+        assert_eq!(f["tags"], list(&["rust", "a, b", "c"]));
+        assert_eq!(f["empty"], list(&[]));
+        assert_eq!(f["aliases"], list(&["one", "two: 2"]));
+        assert_eq!(f["status"], str("open"));
+
+        let f = fields("+++\ntags = [\"x\", \"y\"]\npriority = 2\n+++\n");
+        assert_eq!(f["tags"], list(&["x", "y"]));
+        assert_eq!(f["priority"], str("2"));
+    }
+
+    #[test]
+    fn delimiters() {
+        let (f, rest) =
+            FrontMatter::parse("---\r\ntitle: a\r\ntags: [b]\r\n---\r\nbody\r\n").unwrap();
+        assert_eq!(f.get("title"), Some(&str("a")));
+        assert_eq!(f.get("tags"), Some(&list(&["b"])));
+        assert_eq!(f.source(), "---\r\ntitle: a\r\ntags: [b]\r\n---\r\n");
+        assert_eq!(rest, "body\r\n");
+
+        let (f, rest) = FrontMatter::parse("---\ntitle: a\n...\nbody\n").unwrap();
+        assert_eq!(f.get("title"), Some(&str("a")));
+        assert_eq!(rest, "body\n");
+
+        // "..." ends only a YAML block, and a block must be closed.
+        assert!(FrontMatter::parse("+++\ntitle = \"a\"\n...\n").is_none());
+        assert!(FrontMatter::parse("---\ntitle: a\n").is_none());
+        assert!(FrontMatter::parse("title: a\n---\n").is_none());
+    }
+
+    #[test]
+    fn insert() {
+        assert_eq!(
+            FrontMatter::insert(
+                "---\ntitle: old\ntags:\n  - a\nstatus: open\n---\nbody\n",
+                Some("new: title"),
+                &["b".to_owned(), "a".to_owned()],
+            ),
+            "---\nstatus: open\ntitle: \"new: title\"\ntags: [a, b]\n---\nbody\n"
+        );
+        assert_eq!(
+            FrontMatter::insert("+++\r\ntitle = \"old\"\r\n+++\r\n", Some("new"), &[]),
+            "+++\r\ntitle = \"new\"\r\n+++\r\n"
+        );
+        assert_eq!(
+            FrontMatter::insert("no front matter\n", Some("new"), &[]),
+            "no front matter\n"
+        );
+    }
+
+    #[test]
+    fn retag_keeps_comments_and_indentation() {
+        let rename = |t: &str| match t {
+            "old" => Some("new".to_owned()),
+            "gone" => None,
+            t => Some(t.to_owned()),
+        };
+
+        assert_eq!(
+            FrontMatter::retag("---\ntags: [old, gone, keep] # topics\n---\nbody\n", rename),
+            "---\ntags: [new, keep] # topics\n---\nbody\n"
+        );
+        assert_eq!(
+            FrontMatter::retag(
+                "---\ntags: # topics\n    - old # renamed\n    - gone\n    - new\ntitle: a\n---\n",
+                rename
+            ),
+            "---\ntags: # topics\n    - new # renamed\ntitle: a\n---\n"
+        );
+        assert_eq!(
+            FrontMatter::retag("+++\r\ntags = [\"old\"]\r\n+++\r\n", rename),
+            "+++\r\ntags = [\"new\"]\r\n+++\r\n"
+        );
+        assert_eq!(FrontMatter::retag("[old] title\n", rename), "[old] title\n");
+    }
+}
@@ -0,0 +1,165 @@
+#[allow(unused)]
+use {
+    super::{error::MemoError, front::FrontMatter, memo::FileName},
+    chrono::{DateTime, Local, NaiveDateTime},
+    error_stack::{Report, Result, ResultExt},
+    jlogger_tracing::{
+        jdebug, jerror, jinfo, jtrace, jwarn, JloggerBuilder, LevelFilter, LogTimeFormat,
+    },
+    std::{fs, io::Write, path::Path},
+};
+
+/// A saved content of a note.
+pub struct Revision {
+    /// Revisions of a note are numbered from 1, the latest has the largest number.
+    pub number: usize,
+    /// When the content was written.
+    pub time: NaiveDateTime,
+    pub path: String,
+}
+
+impl Revision {
+    pub fn content(&self) -> Result<String, MemoError> {
+        fs::read_to_string(&self.path).map_err(|e| {
+            Report::new(MemoError::IOError)
+                .attach_printable(format!("Failed to read {}: {e}", self.path))
+        })
+    }
+
+    /// Title of the content, like the title of a note.
+    pub fn title(&self) -> String {
+        let content = self.content().unwrap_or_default();
+        let (front, rest) = match FrontMatter::parse(&content) {
+            Some((front, rest)) => (front, rest),
+            None => (FrontMatter::default(), content.as_str()),
+        };
+
+        front
+            .get("title")
+            .and_then(|t| t.as_str())
+            .or_else(|| rest.lines().find(|l| !l.trim().is_empty()))
+            .unwrap_or_default()
+            .to_owned()
+    }
+
+    pub fn lines(&self) -> usize {
+        self.content().map(|c| c.lines().count()).unwrap_or(0)
+    }
+}
+
+/// Previous contents of notes kept under `{root}/history/ID/`, one file per revision
+/// like "3-20260131103000.txt" with the time the content was written.
+pub struct History;
+
+impl History {
+    fn dir(root: &str, id: &str) -> String {
+        format!("{root}/history/{id}")
+    }
+
+    fn id(file: &str) -> Result<String, MemoError> {
+        let name = Path::new(file)
+            .file_name()
+            .and_then(|a| a.to_str())
+            .unwrap_or(file);
+
+        Ok(FileName::from_file_name(name)?.id())
+    }
+
+    /// Last modification time of `file`.
+    pub fn modified(file: &str) -> Option<NaiveDateTime> {
+        let t = fs::metadata(file).and_then(|m| m.modified()).ok()?;
+        Some(DateTime::<Local>::from(t).naive_local())
+    }
+
+    /// Save `content` of the note `file` as a new revision, `time` is when it was written.
+    pub fn save(
+        root: &str,
+        file: &str,
+        content: &str,
+        time: NaiveDateTime,
+    ) -> Result<Revision, MemoError> {
+        let id = History::id(file)?;
+        let dir = History::dir(root, &id);
+        fs::create_dir_all(&dir).map_err(|e| {
+            Report::new(MemoError::IOError).attach_printable(format!("Failed to create {dir}: {e}"))
+        })?;
+
+        let number = History::list(root, &id).last().map_or(1, |r| r.number + 1);
+        let suffix = Path::new(file)
+            .extension()
+            .and_then(|a| a.to_str())
+            .unwrap_or("txt");
+        let path = format!("{dir}/{number}-{}.{suffix}", time.format("%Y%m%d%H%M%S"));
+
+        fs::write(&path, content).map_err(|e| {
+            Report::new(MemoError::IOError).attach_printable(format!("Failed to write {path}: {e}"))
+        })?;
+
+        jdebug!("Revision {number} of {file} is saved to {path}.");
+        Ok(Revision { number, time, path })
+    }
+
+    /// Revisions of the note `id`, the oldest first.
+    pub fn list(root: &str, id: &str) -> Vec<Revision> {
+        let mut list: Vec<Revision> = fs::read_dir(History::dir(root, id))
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter_map(|f| {
+                let path = f.path();
+                let stem = path.file_stem()?.to_str()?;
+                let (number, time) = stem.split_once('-')?;
+
+                Some(Revision {
+                    number: number.parse().ok()?,
+                    time: NaiveDateTime::parse_from_str(time, "%Y%m%d%H%M%S").ok()?,
+                    path: path.to_string_lossy().into_owned(),
+                })
+            })
+            .collect();
+
+        list.sort_by_key(|r| r.number);
+        list
+    }
+
+    /// Revision `number` of the note `id`, the latest one if `number` is not given.
+    pub fn get(root: &str, id: &str, number: Option<usize>) -> Result<Revision, MemoError> {
+        let list = History::list(root, id);
+        let rev = match number {
+            Some(n) => list.into_iter().find(|r| r.number == n),
+            None => list.into_iter().last(),
+        };
+
+        rev.ok_or_else(|| {
+            let rev = number.map(|n| format!(" {n}")).unwrap_or_default();
+            Report::new(MemoError::InvalidValue)
+                .attach_printable(format!("No revision{rev} of {id}, see \"memo log\"."))
+        })
+    }
+
+    /// Replace the content of the note `file` with `rev`, the current content is saved as a
+    /// new revision first.
+    pub fn restore(root: &str, file: &str, rev: &Revision) -> Result<Revision, MemoError> {
+        let current = fs::read_to_string(file).map_err(|e| {
+            Report::new(MemoError::IOError).attach_printable(format!("Failed to read {file}: {e}"))
+        })?;
+        let time = History::modified(file).unwrap_or_else(|| Local::now().naive_local());
+        let saved = History::save(root, file, &current, time)?;
+
+        let content = rev.content()?;
+        let tmp = format!("{file}.tmp");
+        let write = || -> std::io::Result<()> {
+            let mut f = fs::File::create(&tmp)?;
+            f.write_all(content.as_bytes())?;
+            f.sync_all()?;
+            fs::rename(&tmp, file)
+        };
+
+        write().map_err(|e| {
+            let _ = fs::remove_file(&tmp);
+            Report::new(MemoError::IOError).attach_printable(format!("Failed to write {file}: {e}"))
+        })?;
+
+        Ok(saved)
+    }
+}
@@ -22,6 +22,10 @@ use {
 
 pub struct Html;
 
+/// State of a list while converting html to text, `None` for `<ul>` and the next number
+/// for `<ol>`.
+type ListState = Option<usize>;
+
 impl Html {
     pub fn h1(s: &str) -> String {
         format!("<H1>{s}</H1>")
@@ -69,4 +73,233 @@ impl Html {
         let re = Regex::new(" +").unwrap();
         clear(re, &fix, " ")
     }
+
+    /// Decode character references like `&amp;`, `&#39;` and `&#x27;`.
+    pub fn decode_entities(s: &str) -> String {
+        let mut out = String::with_capacity(s.len());
+        let mut rest = s;
+
+        while let Some(pos) = rest.find('&') {
+            out.push_str(&rest[..pos]);
+            rest = &rest[pos..];
+
+            let decoded = rest.find(';').filter(|&end| end <= 10).and_then(|end| {
+                let name = &rest[1..end];
+                let c = match name {
+                    "amp" => Some('&'),
+                    "lt" => Some('<'),
+                    "gt" => Some('>'),
+                    "quot" => Some('"'),
+                    "apos" => Some('\''),
+                    "nbsp" => Some(' '),
+                    "copy" => Some('©'),
+                    "hellip" => Some('…'),
+                    "mdash" => Some('—'),
+                    "ndash" => Some('–'),
+                    _ => {
+                        if let Some(hex) = name.strip_prefix("#x").or(name.strip_prefix("#X")) {
+                            u32::from_str_radix(hex, 16).ok().and_then(char::from_u32)
+                        } else if let Some(dec) = name.strip_prefix('#') {
+                            dec.parse::<u32>().ok().and_then(char::from_u32)
+                        } else {
+                            None
+                        }
+                    }
+                };
+
+                c.map(|c| (c, end))
+            });
+
+            match decoded {
+                Some((c, end)) => {
+                    out.push(c);
+                    rest = &rest[end + 1..];
+                }
+                None => {
+                    out.push('&');
+                    rest = &rest[1..];
+                }
+            }
+        }
+
+        out.push_str(rest);
+        out
+    }
+
+    /// Value of attribute `name` in the inside of a tag like `a href="x"`.
+    fn attribute(tag: &str, name: &str) -> Option<String> {
+        let re = Regex::new(&format!(
+            r#"(?i)\b{name}\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s>]+))"#
+        ))
+        .ok()?;
+
+        re.captures(tag).and_then(|c| {
+            c.get(1)
+                .or(c.get(2))
+                .or(c.get(3))
+                .map(|m| Html::decode_entities(m.as_str()))
+        })
+    }
+
+    /// Convert html to readable text.
+    ///
+    /// Whitespaces are collapsed except in `<pre>`, block elements start new lines, list items
+    /// are bulleted or numbered and links are followed by their target.
+    pub fn to_text(html: &str) -> String {
+        let mut out = String::new();
+        let mut lists: Vec<ListState> = vec![];
+        let mut links: Vec<Option<String>> = vec![];
+        let mut pre = 0_usize;
+        let mut skip = 0_usize;
+        let mut rest = html;
+
+        let newline = |out: &mut String, blank: bool| {
+            while out.ends_with(' ') {
+                out.pop();
+            }
+
+            if out.is_empty() {
+                return;
+            }
+
+            let want = if blank { "\n\n" } else { "\n" };
+            while !out.ends_with(want) {
+                out.push('\n');
+            }
+        };
+
+        while !rest.is_empty() {
+            let Some(lt) = rest.find('<') else {
+                Html::push_text(&mut out, rest, pre > 0, skip > 0);
+                break;
+            };
+
+            Html::push_text(&mut out, &rest[..lt], pre > 0, skip > 0);
+            rest = &rest[lt..];
+
+            if rest.starts_with("<!--") {
+                rest = rest.find("-->").map(|e| &rest[e + 3..]).unwrap_or("");
+                continue;
+            }
+
+            let Some(gt) = rest.find('>') else {
+                Html::push_text(&mut out, rest, pre > 0, skip > 0);
+                break;
+            };
+
+            let inside = &rest[1..gt];
+            rest = &rest[gt + 1..];
+
+            let closing = inside.starts_with('/');
+            let name = inside
+                .trim_start_matches('/')
+                .split(|c: char| c.is_whitespace() || c == '/')
+                .next()
+                .unwrap_or("")
+                .to_lowercase();
+
+            match (name.as_str(), closing) {
+                ("script" | "style" | "head" | "title", false) => skip += 1,
+                ("script" | "style" | "head" | "title", true) => skip = skip.saturating_sub(1),
+                _ if skip > 0 => {}
+                ("br", _) => {
+                    while out.ends_with(' ') {
+                        out.pop();
+                    }
+                    out.push('\n');
+                }
+                ("hr", _) => {
+                    newline(&mut out, false);
+                    out.push_str("----------------------------------------\n");
+                }
+                ("pre", false) => {
+                    newline(&mut out, true);
+                    pre += 1;
+                }
+                ("pre", true) => {
+                    pre = pre.saturating_sub(1);
+                    newline(&mut out, true);
+                }
+                ("p" | "h1" | "h2" | "h3" | "h4" | "h5" | "h6" | "blockquote" | "table", _) => {
+                    newline(&mut out, true)
+                }
+                ("ul", false) => {
+                    newline(&mut out, false);
+                    lists.push(None);
+                }
+                ("ol", false) => {
+                    newline(&mut out, false);
+                    lists.push(Some(1));
+                }
+                ("ul" | "ol", true) => {
+                    lists.pop();
+                    newline(&mut out, lists.is_empty());
+                }
+                ("li", false) => {
+                    newline(&mut out, false);
+                    let indent = "  ".repeat(lists.len().max(1));
+                    match lists.last_mut() {
+                        Some(Some(n)) => {
+                            out.push_str(&format!("{indent}{n}. "));
+                            *n += 1;
+                        }
+                        _ => out.push_str(&format!("{indent}* ")),
+                    }
+                }
+                (
+                    "div" | "section" | "article" | "header" | "footer" | "tr" | "li" | "dt" | "dd",
+                    _,
+                ) => newline(&mut out, false),
+                ("td" | "th", true) => out.push('\t'),
+                ("a", false) => links.push(Html::attribute(inside, "href")),
+                ("a", true) => {
+                    if let Some(Some(href)) = links.pop() {
+                        if !out.trim_end().ends_with(href.as_str()) {
+                            out.push_str(&format!(" <{href}>"));
+                        }
+                    }
+                }
+                ("img", _) => {
+                    if let Some(alt) = Html::attribute(inside, "alt") {
+                        out.push_str(&format!("[{alt}]"));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        // Blocks next to each other are separated by a single blank line.
+        let mut lines: Vec<&str> = vec![];
+        for line in out.lines().map(|l| l.trim_end()) {
+            if !(line.is_empty() && lines.last().is_some_and(|l| l.is_empty())) {
+                lines.push(line);
+            }
+        }
+
+        let mut text = lines.join("\n").trim_matches('\n').to_owned();
+        text.push('\n');
+        text
+    }
+
+    fn push_text(out: &mut String, text: &str, pre: bool, skip: bool) {
+        if skip {
+            return;
+        }
+
+        let text = Html::decode_entities(text);
+        if pre {
+            out.push_str(&text);
+            return;
+        }
+
+        for c in text.chars() {
+            if c.is_whitespace() {
+                if !out.is_empty() && !out.ends_with(' ') && !out.ends_with('\n') {
+                    out.push(' ');
+                }
+            } else {
+                out.push(c);
+            }
+        }
+    }
 }
@@ -0,0 +1,458 @@
+#[allow(unused)]
+use {
+    super::{
+        error::MemoError,
+        memo::{edit_distance, is_cjk, MatchCondition, MatchMode, Matcher, MemoEntry},
+    },
+    error_stack::{Report, Result, ResultExt},
+    jlogger_tracing::{
+        jdebug, jerror, jinfo, jtrace, jwarn, JloggerBuilder, LevelFilter, LogTimeFormat,
+    },
+    std::{
+        cell::OnceCell,
+        collections::{BTreeMap, BTreeSet, HashSet},
+        fs,
+        io::{BufRead, BufReader, BufWriter, Write},
+        path::Path,
+        time::UNIX_EPOCH,
+    },
+};
+
+const FILES: &str = "files";
+const TERMS: &str = "terms";
+const VERSION: &str = "version";
+
+/// Format of the index files, an index of another format is rebuilt.
+const FORMAT: &str = "1";
+
+/// What the index keeps of a note, enough to list it without reading the file.
+struct Indexed {
+    mtime: u128,
+    title: String,
+    tags: Vec<String>,
+    /// Front matter block as it is written.
+    front: String,
+}
+
+/// Every suffix of every term in order, to find the terms including a part of a word.
+struct Suffixes {
+    terms: Vec<String>,
+    /// Index in `terms` and byte offset of each suffix.
+    suffixes: Vec<(usize, usize)>,
+}
+
+impl Suffixes {
+    fn new(terms: &BTreeMap<String, BTreeSet<String>>) -> Self {
+        let terms: Vec<String> = terms.keys().cloned().collect();
+        let mut suffixes: Vec<(usize, usize)> = terms
+            .iter()
+            .enumerate()
+            .flat_map(|(i, t)| t.char_indices().map(move |(o, _)| (i, o)))
+            .collect();
+        suffixes.sort_by(|&(a, i), &(b, j)| terms[a][i..].cmp(&terms[b][j..]));
+
+        Self { terms, suffixes }
+    }
+
+    /// Terms including `part`, found by the suffixes starting with it.
+    fn containing<'a>(&'a self, part: &'a str) -> impl Iterator<Item = &'a str> {
+        let start = self
+            .suffixes
+            .partition_point(|&(t, o)| &self.terms[t][o..] < part);
+
+        self.suffixes[start..]
+            .iter()
+            .take_while(move |&&(t, o)| self.terms[t][o..].starts_with(part))
+            .map(|&(t, _)| self.terms[t].as_str())
+    }
+}
+
+/// Persistent inverted index of the notes stored under `<root>/index/`.
+///
+/// `files` records every indexed note, one `<mtime>\t<file name>\t<title>\t<tags>\t<front
+/// matter>` per line with tabs and newlines escaped, so that a note not modified since it was
+/// indexed is listed without reading it. `terms` maps every word to the notes including it,
+/// one `<word>\t<file name>\t<file name>...` per line. `version` holds the format.
+pub struct Index {
+    dir: String,
+    files: BTreeMap<String, Indexed>,
+    terms: BTreeMap<String, BTreeSet<String>>,
+    /// Built on the first search for a part of a word.
+    suffixes: OnceCell<Suffixes>,
+    dirty: bool,
+}
+
+impl Index {
+    /// Modification time of `path` in nanoseconds, 0 if it is not known.
+    pub fn mtime(path: &str) -> u128 {
+        fs::metadata(path)
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_nanos())
+            .unwrap_or(0)
+    }
+
+    fn read_lines(file: &str) -> Result<Vec<String>, MemoError> {
+        let f = fs::File::open(file).map_err(|e| {
+            Report::new(MemoError::IOError).attach_printable(format!("Failed to open {file}: {e}"))
+        })?;
+
+        BufReader::new(f)
+            .lines()
+            .collect::<std::result::Result<Vec<String>, _>>()
+            .map_err(|e| {
+                Report::new(MemoError::IOError)
+                    .attach_printable(format!("Failed to read {file}: {e}"))
+            })
+    }
+
+    fn escape(s: &str) -> String {
+        s.replace('\\', "\\\\")
+            .replace('\t', "\\t")
+            .replace('\n', "\\n")
+            .replace('\r', "\\r")
+    }
+
+    fn unescape(s: &str) -> String {
+        let mut out = String::with_capacity(s.len());
+        let mut chars = s.chars();
+        while let Some(c) = chars.next() {
+            if c != '\\' {
+                out.push(c);
+                continue;
+            }
+
+            match chars.next() {
+                Some('t') => out.push('\t'),
+                Some('n') => out.push('\n'),
+                Some('r') => out.push('\r'),
+                Some(c) => out.push(c),
+                None => out.push('\\'),
+            }
+        }
+
+        out
+    }
+
+    fn read(&mut self) -> Result<(), MemoError> {
+        for line in Index::read_lines(&format!("{}/{FILES}", self.dir))? {
+            let invalid = || {
+                Report::new(MemoError::InvalidValue)
+                    .attach_printable(format!("Invalid index entry `{line}`"))
+            };
+
+            let mut it = line.split('\t');
+            let (Some(mtime), Some(name), Some(title), Some(tags), Some(front), None) = (
+                it.next(),
+                it.next(),
+                it.next(),
+                it.next(),
+                it.next(),
+                it.next(),
+            ) else {
+                return Err(invalid());
+            };
+
+            let mtime = mtime.parse::<u128>().map_err(|e| {
+                invalid().attach_printable(format!("Invalid modification time: {e}"))
+            })?;
+
+            self.files.insert(
+                name.to_owned(),
+                Indexed {
+                    mtime,
+                    title: Index::unescape(title),
+                    tags: tags.split_whitespace().map(|a| a.to_owned()).collect(),
+                    front: Index::unescape(front),
+                },
+            );
+        }
+
+        for line in Index::read_lines(&format!("{}/{TERMS}", self.dir))? {
+            let mut it = line.split('\t');
+            if let Some(term) = it.next() {
+                self.terms
+                    .insert(term.to_owned(), it.map(|a| a.to_owned()).collect());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Load the index of `root`, an empty index is returned if there is none yet or it is broken.
+    pub fn load(root: &str) -> Self {
+        let mut index = Self {
+            dir: format!("{root}/index"),
+            files: BTreeMap::new(),
+            terms: BTreeMap::new(),
+            suffixes: OnceCell::new(),
+            dirty: false,
+        };
+
+        if Path::new(&index.dir).is_dir() {
+            let version = fs::read_to_string(format!("{}/{VERSION}", index.dir));
+            if version.is_err() {
+                // Nothing is indexed yet.
+                index.clear();
+            } else if version.is_ok_and(|v| v.trim() != FORMAT) {
+                jinfo!("Index format is changed, rebuild it.");
+                index.clear();
+            } else if let Err(e) = index.read() {
+                jwarn!("Index is broken, rebuild it:\n{:?}", e);
+                index.clear();
+            }
+        }
+
+        index
+    }
+
+    fn clear(&mut self) {
+        self.files.clear();
+        self.terms.clear();
+        self.suffixes = OnceCell::new();
+        self.dirty = true;
+    }
+
+    fn remove(&mut self, name: &str) {
+        self.files.remove(name);
+        self.terms.retain(|_, files| {
+            files.remove(name);
+            !files.is_empty()
+        });
+        self.suffixes = OnceCell::new();
+        self.dirty = true;
+    }
+
+    /// Parts of `word` split at CJK characters, which are words of their own for the matcher
+    /// like "rust" and "の" of "rustの".
+    fn parts(word: &str) -> Vec<String> {
+        let mut parts = vec![];
+        let mut part = String::new();
+        for c in word.chars() {
+            if is_cjk(c) {
+                parts.extend((!part.is_empty()).then(|| std::mem::take(&mut part)));
+                parts.push(c.to_string());
+            } else {
+                part.push(c);
+            }
+        }
+
+        parts.extend((!part.is_empty()).then_some(part));
+        parts
+    }
+
+    fn insert(&mut self, entry: &MemoEntry, mtime: u128) {
+        let name = entry.file_name().to_owned();
+        let mut words = Matcher::words(entry.title(), true)
+            .into_iter()
+            .chain(Matcher::words(entry.body(), true))
+            .chain(Matcher::words(&entry.tags(), true))
+            .chain(Matcher::words(&entry.aliases().join(" "), true))
+            .collect::<BTreeSet<String>>();
+
+        let parts: Vec<String> = words
+            .iter()
+            .filter(|w| w.chars().any(is_cjk))
+            .flat_map(|w| Index::parts(w))
+            .collect();
+        words.extend(parts);
+
+        for word in words {
+            self.terms.entry(word).or_default().insert(name.clone());
+        }
+
+        self.files.insert(
+            name,
+            Indexed {
+                mtime,
+                title: entry.title().to_owned(),
+                tags: entry.tag_list().to_vec(),
+                front: entry.front().to_owned(),
+            },
+        );
+        self.suffixes = OnceCell::new();
+        self.dirty = true;
+    }
+
+    /// The note `file` as it is indexed, `None` if it is not indexed or is modified since
+    /// then.
+    pub fn entry(&self, file: &str, mtime: u128) -> Option<MemoEntry> {
+        let name = Path::new(file).file_name()?.to_str()?;
+        let indexed = self.files.get(name).filter(|i| i.mtime == mtime)?;
+
+        MemoEntry::indexed(
+            file.to_owned(),
+            &indexed.title,
+            &indexed.tags,
+            &indexed.front,
+        )
+        .ok()
+    }
+
+    /// Bring the index up to date with `entries`, only notes modified since they were
+    /// indexed are processed again.
+    pub fn update(&mut self, entries: &[MemoEntry]) {
+        let names: HashSet<&str> = entries.iter().map(|e| e.file_name()).collect();
+        let removed: Vec<String> = self
+            .files
+            .keys()
+            .filter(|&a| !names.contains(a.as_str()))
+            .cloned()
+            .collect();
+
+        for name in removed {
+            jdebug!("Remove {name} from index");
+            self.remove(&name);
+        }
+
+        for entry in entries {
+            let mtime = Index::mtime(entry.full_path());
+            match self.files.get(entry.file_name()) {
+                Some(i) if i.mtime == mtime => {}
+                Some(_) => {
+                    jdebug!("Update {} in index", entry.file_name());
+                    self.remove(entry.file_name());
+                    self.insert(entry, mtime);
+                }
+                None => {
+                    jdebug!("Add {} to index", entry.file_name());
+                    self.insert(entry, mtime);
+                }
+            }
+        }
+    }
+
+    /// Drop everything and index `entries` from scratch.
+    pub fn rebuild(&mut self, entries: &[MemoEntry]) {
+        self.clear();
+        self.update(entries);
+    }
+
+    pub fn save(&mut self) -> Result<(), MemoError> {
+        if !self.dirty {
+            return Ok(());
+        }
+
+        fs::create_dir_all(&self.dir).map_err(|e| {
+            Report::new(MemoError::IOError).attach_printable(format!(
+                "Failed to create index directory {}: {e}",
+                self.dir
+            ))
+        })?;
+
+        let write = |name: &str, lines: Vec<String>| -> Result<(), MemoError> {
+            let file = format!("{}/{name}", self.dir);
+            let tmp = format!("{file}.tmp");
+            let f = fs::File::create(&tmp).map_err(|e| {
+                Report::new(MemoError::IOError)
+                    .attach_printable(format!("Failed to create {tmp}: {e}"))
+            })?;
+
+            let mut w = BufWriter::new(f);
+            for l in lines {
+                writeln!(w, "{l}").map_err(|e| {
+                    Report::new(MemoError::IOError)
+                        .attach_printable(format!("Failed to write {tmp}: {e}"))
+                })?;
+            }
+
+            w.flush().map_err(|e| {
+                Report::new(MemoError::IOError)
+                    .attach_printable(format!("Failed to write {tmp}: {e}"))
+            })?;
+
+            fs::rename(&tmp, &file).map_err(|e| {
+                Report::new(MemoError::IOError)
+                    .attach_printable(format!("Failed to rename {tmp} to {file}: {e}"))
+            })
+        };
+
+        write(
+            FILES,
+            self.files
+                .iter()
+                .map(|(name, i)| {
+                    format!(
+                        "{}\t{name}\t{}\t{}\t{}",
+                        i.mtime,
+                        Index::escape(&i.title),
+                        i.tags.join(" "),
+                        Index::escape(&i.front)
+                    )
+                })
+                .collect(),
+        )?;
+
+        write(
+            TERMS,
+            self.terms
+                .iter()
+                .map(|(term, files)| {
+                    let mut l = term.clone();
+                    for f in files {
+                        l.push('\t');
+                        l.push_str(f);
+                    }
+                    l
+                })
+                .collect(),
+        )?;
+
+        write(VERSION, vec![FORMAT.to_owned()])?;
+
+        self.dirty = false;
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    /// Notes which may match `key`, `None` if the index can not tell and all notes have to
+    /// be checked.
+    ///
+    /// Every word of the key must be a word of the note with "--word", a part of one
+    /// otherwise, or be close enough to one in fuzzy mode, so the result is a superset of the
+    /// notes really matching the key.
+    pub fn candidates(&self, key: &str, condition: MatchCondition) -> Option<HashSet<&str>> {
+        if !matches!(condition.mode, MatchMode::Literal | MatchMode::Fuzzy) {
+            return None;
+        }
+
+        let mut words = Matcher::words(key, true);
+        if condition.mode == MatchMode::Literal && condition.match_word {
+            words = words.iter().flat_map(|w| Index::parts(w)).collect();
+        }
+
+        let mut result: Option<HashSet<&str>> = None;
+        for word in words {
+            let mut found = HashSet::new();
+            if condition.mode == MatchMode::Fuzzy {
+                let distance = condition.distance_for(&word);
+                for (term, files) in &self.terms {
+                    if edit_distance(&word, term, distance).is_some() {
+                        found.extend(files.iter().map(|a| a.as_str()));
+                    }
+                }
+            } else if condition.match_word {
+                if let Some(files) = self.terms.get(&word) {
+                    found.extend(files.iter().map(|a| a.as_str()));
+                }
+            } else {
+                let suffixes = self.suffixes.get_or_init(|| Suffixes::new(&self.terms));
+                for term in suffixes.containing(&word) {
+                    found.extend(self.terms[term].iter().map(|a| a.as_str()));
+                }
+            }
+
+            result = Some(match result {
+                Some(r) => r.intersection(&found).copied().collect(),
+                None => found,
+            });
+        }
+
+        jtrace!("candidates for {key}: {:?}", result);
+        result
+    }
+}
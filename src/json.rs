@@ -0,0 +1,163 @@
+#[allow(unused)]
+use {
+    super::{front::Value, history::Revision, memo::MemoEntry, tag::TagStat, trash::Trashed},
+    chrono::{Local, NaiveDateTime, TimeZone},
+    std::fmt::Write,
+};
+
+pub struct Json;
+
+impl Json {
+    pub fn string(s: &str) -> String {
+        let mut out = String::with_capacity(s.len() + 2);
+
+        out.push('"');
+        for c in s.chars() {
+            match c {
+                '"' => out.push_str("\\\""),
+                '\\' => out.push_str("\\\\"),
+                '\n' => out.push_str("\\n"),
+                '\r' => out.push_str("\\r"),
+                '\t' => out.push_str("\\t"),
+                c if (c as u32) < 0x20 => {
+                    let _ = write!(out, "\\u{:04x}", c as u32);
+                }
+                c => out.push(c),
+            }
+        }
+        out.push('"');
+
+        out
+    }
+
+    pub fn array(items: &[String]) -> String {
+        format!("[{}]", items.join(","))
+    }
+
+    /// Build an object from already serialized values.
+    pub fn object(fields: &[(&str, String)]) -> String {
+        let fields: Vec<String> = fields
+            .iter()
+            .map(|(k, v)| format!("{}:{v}", Json::string(k)))
+            .collect();
+
+        format!("{{{}}}", fields.join(","))
+    }
+
+    /// A front matter value, always as strings since their types are not known.
+    pub fn value(value: &Value) -> String {
+        match value {
+            Value::Str(s) => Json::string(s),
+            Value::List(l) => {
+                Json::array(&l.iter().map(|a| Json::string(a)).collect::<Vec<String>>())
+            }
+        }
+    }
+
+    /// Local time in RFC 3339, or null.
+    pub fn datetime(t: Option<NaiveDateTime>) -> String {
+        t.and_then(|t| Local.from_local_datetime(&t).earliest())
+            .map(|t| Json::string(&t.to_rfc3339()))
+            .unwrap_or("null".to_owned())
+    }
+
+    /// Serialize a tag, the tags in its subtree are added as "children" if given.
+    pub fn tag(stat: &TagStat, children: Option<Vec<String>>) -> String {
+        let mut fields = vec![
+            ("tag", Json::string(&stat.name)),
+            ("count", stat.count.to_string()),
+            ("first", Json::datetime(stat.first)),
+            ("last", Json::datetime(stat.last)),
+        ];
+
+        if let Some(children) = children {
+            fields.push(("children", Json::array(&children)));
+        }
+
+        Json::object(&fields)
+    }
+
+    /// Serialize tags in tree order as nested objects, starting from `stats[*i]`.
+    pub fn tag_tree(stats: &[TagStat], i: &mut usize, depth: usize) -> Vec<String> {
+        let mut items = vec![];
+
+        while *i < stats.len() && stats[*i].depth == depth {
+            let stat = &stats[*i];
+            *i += 1;
+            let children = Json::tag_tree(stats, i, depth + 1);
+            items.push(Json::tag(stat, Some(children)));
+        }
+
+        items
+    }
+
+    /// A saved revision of a note.
+    pub fn revision(r: &Revision) -> String {
+        Json::object(&[
+            ("rev", r.number.to_string()),
+            ("written", Json::datetime(Some(r.time))),
+            ("lines", r.lines().to_string()),
+            ("title", Json::string(&r.title())),
+            ("path", Json::string(&r.path)),
+        ])
+    }
+
+    /// A note in the trash.
+    pub fn trashed(t: &Trashed) -> String {
+        Json::object(&[
+            ("id", Json::string(&t.id)),
+            ("title", Json::string(&t.title)),
+            ("deleted", Json::datetime(Some(t.deleted))),
+            ("reason", Json::string(&t.reason)),
+            ("origin", Json::string(&t.origin)),
+            ("path", Json::string(&t.path)),
+        ])
+    }
+
+    /// Serialize a note, its score is included if given and the body if requested.
+    pub fn entry(entry: &MemoEntry, score: Option<f64>, with_body: bool) -> String {
+        let tags: Vec<String> = entry
+            .tag_list()
+            .iter()
+            .map(|t| Json::string(t.trim_matches('[').trim_matches(']')))
+            .collect();
+
+        let created = Json::datetime(entry.created());
+
+        let mut fields = vec![
+            ("id", Json::string(entry.id())),
+            ("title", Json::string(entry.title())),
+            ("tags", Json::array(&tags)),
+            ("created", created),
+            ("path", Json::string(entry.full_path())),
+            ("kind", Json::string(entry.kind())),
+        ];
+
+        if let Some(updated) = entry.updated() {
+            fields.push(("updated", Json::datetime(Some(updated))));
+        }
+
+        let aliases: Vec<String> = entry.aliases().iter().map(|a| Json::string(a)).collect();
+        if !aliases.is_empty() {
+            fields.push(("aliases", Json::array(&aliases)));
+        }
+
+        let meta: Vec<(&str, String)> = entry
+            .meta()
+            .map(|(k, v)| (k.as_str(), Json::value(v)))
+            .collect();
+        if !meta.is_empty() {
+            fields.push(("meta", Json::object(&meta)));
+        }
+
+        if let Some(score) = score {
+            fields.push(("score", format!("{score:.2}")));
+        }
+
+        if with_body {
+            fields.push(("body", Json::string(entry.body())));
+        }
+
+        Json::object(&fields)
+    }
+}
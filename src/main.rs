@@ -1,32 +1,56 @@
 use regex::Regex;
 
+mod date;
+mod doctor;
 mod error;
+mod front;
+mod history;
 mod html;
+mod index;
+mod json;
+mod markdown;
 mod memo;
+mod query;
+mod rank;
+mod tag;
+mod template;
+mod trash;
+mod tty;
 
 #[allow(unused)]
 use {
     chrono::Local,
-    clap::Parser,
+    clap::{error::ErrorKind, Args, CommandFactory, Parser, Subcommand},
+    date::DateRange,
+    doctor::Doctor,
     error::MemoError,
     error_stack::{Report, Result, ResultExt},
+    front::FrontMatter,
+    history::History,
     html::Html,
     jlogger_tracing::{
         jdebug, jerror, jinfo, jtrace, jwarn, JloggerBuilder, LevelFilter, LogTimeFormat,
     },
-    memo::{FileName, MatchCondition, Memo, MemoEntry},
+    json::Json,
+    markdown::Markdown,
+    memo::{FileName, MatchCondition, MatchMode, Memo, MemoEntry, SHORT_ID_LEN},
+    query::Query,
+    rank::{Scorer, SortKey},
     std::{
         boxed::Box,
-        collections::VecDeque,
+        collections::{BTreeMap, VecDeque},
         env,
         ffi::{CStr, CString},
         fmt::Display,
         fs,
-        io::{self, Cursor},
+        io::{self, Cursor, IsTerminal, Read, Write},
         mem,
-        process::Command,
+        process::{Command, Stdio},
         sync::atomic::{AtomicI32, Ordering},
     },
+    tag::{Retag, TagStat},
+    trash::Trash,
+    tty::{OutputMode, Tty},
 };
 
 #[derive(Parser)]
@@ -37,6 +61,8 @@ use {
 Environment variable
   EDITOR : editor used to create a new note (default: vim).
   BROWSER: browser used to display notes (default: w3m).
+  PAGER  : pager used by show --pager (default: less -R).
+  NO_COLOR: disable colours of the terminal output if set.
 
 {usage-heading} {usage}
 
@@ -44,24 +70,64 @@ Environment variable
 ")]
 struct Cli {
     /// Root path to store memos, default is "$HOME/.memo/"
-    #[arg(short, long)]
+    #[arg(short, long, global = true)]
     path: Option<String>,
 
-    /// Add text memo
+    /// Add text memo, same as "memo new"
     #[arg(short = 'a', long, conflicts_with = "add_html_memo")]
     add_text_memo: bool,
 
-    /// Add html memo
+    /// Add html memo, same as "memo new -A"
     #[arg(short = 'A', long, conflicts_with = "add_text_memo")]
     add_html_memo: bool,
 
+    /// Delete notes in the search result, same as "memo rm"
+    #[arg(short, long)]
+    delete: bool,
+
+    #[command(flatten)]
+    search: SearchArgs,
+
+    /// How to display the result, "plain" if stdout is not a terminal and "browser" otherwise
+    #[arg(short, long, visible_alias = "format", global = true, value_enum)]
+    output: Option<OutputMode>,
+
+    /// Include the body of notes in JSON output
+    #[arg(long, global = true)]
+    with_body: bool,
+
+    /// Log file
+    #[arg(short, long, global = true)]
+    log: Option<String>,
+
+    #[arg(short, long, global = true, action=clap::ArgAction::Count)]
+    verbose: u8,
+
+    #[command(subcommand)]
+    command: Option<Commands>,
+}
+
+#[derive(Args)]
+struct SearchArgs {
     /// Ignore case sensitivity
     #[arg(short = 'I', long, default_value_t = false)]
     ignore_case: bool,
 
-    /// Delete notes in the search result.
-    #[arg(short, long)]
-    delete: bool,
+    /// Search keys are regular expressions
+    #[arg(short, long, conflicts_with = "glob")]
+    regex: bool,
+
+    /// Search keys are wildcard patterns, "*" matches any characters and "?" matches one character
+    #[arg(short, long, conflicts_with = "regex")]
+    glob: bool,
+
+    /// Match keywords fuzzily, allowing typos
+    #[arg(short, long, conflicts_with_all = ["regex", "glob"])]
+    fuzzy: bool,
+
+    /// Maximum edit distance of fuzzy match, decided by the length of the keyword by default
+    #[arg(long)]
+    max_distance: Option<usize>,
 
     /// Match key as a word
     #[arg(short = 'W', long, default_value_t = false)]
@@ -71,289 +137,1049 @@ struct Cli {
     #[arg(short, long)]
     tag: Option<String>,
 
-    /// Log file
-    #[arg(short, long)]
-    log: Option<String>,
+    /// Only notes created since "SINCE", like 2026-01-01, yesterday or last-7d
+    #[arg(long)]
+    since: Option<String>,
 
-    #[arg(short, long, action=clap::ArgAction::Count)]
-    verbose: u8,
+    /// Only notes created until "UNTIL" (inclusive), like 2026-01-31 or today
+    #[arg(long)]
+    until: Option<String>,
+
+    /// Order of the result
+    #[arg(short, long, value_enum, default_value_t = SortKey::Score)]
+    sort: SortKey,
 
     /// Keyword used to search memo
     args: Option<String>,
 }
 
-#[allow(unused)]
-enum Op {
-    Add,
-    Sub,
-    Mul,
-    Nop,
+#[derive(Subcommand)]
+enum Commands {
+    /// Create a new note with the editor
+    New {
+        /// Create a html note
+        #[arg(short = 'A', long, conflicts_with = "md")]
+        html: bool,
+
+        /// Create a markdown note
+        #[arg(short = 'M', long)]
+        md: bool,
+
+        /// Title of the note, the note is created without the editor
+        #[arg(short = 'T', long)]
+        title: Option<String>,
+
+        /// Tag of the note, can be given multiple times
+        #[arg(short, long = "tag")]
+        tags: Vec<String>,
+
+        /// Body of the note, read from stdin if not given and stdin is not a terminal
+        #[arg(short, long)]
+        body: Option<String>,
+
+        /// Start the note from "templates/TEMPLATE.txt", ".html" or ".md" under the root path
+        #[arg(short = 'm', long, conflicts_with_all = ["title", "tags", "body"])]
+        template: Option<String>,
+    },
+
+    /// Search notes and display them with the browser (default)
+    Search(SearchArgs),
+
+    /// Print notes in the search result, html and markdown notes are converted to text
+    Show {
+        #[command(flatten)]
+        search: SearchArgs,
+
+        /// Page the output with "$PAGER" (default: less -R)
+        #[arg(short = 'P', long)]
+        pager: bool,
+    },
+
+    /// Open a note in the search result with the editor
+    Edit(SearchArgs),
+
+    /// Move notes in the search result to the trash
+    Rm(SearchArgs),
+
+    /// List tags with the number of notes and the first and last date they are used
+    Tags {
+        /// Show hierarchical tags like "proj/web" as a tree
+        #[arg(long)]
+        tree: bool,
+    },
+
+    /// Rename, merge or remove tags in the title of notes
+    Tag {
+        #[command(subcommand)]
+        action: TagCommand,
+
+        /// Show the changes as a diff without writing them
+        #[arg(short = 'n', long, global = true)]
+        dry_run: bool,
+    },
+
+    /// List notes in the search result
+    Ls(SearchArgs),
+
+    /// Rebuild the search index
+    Reindex,
+
+    /// List saved revisions of the note ID, the current content first
+    Log { id: String },
+
+    /// Show changes of the note ID since revision REV, the latest revision if not given
+    Diff { id: String, rev: Option<usize> },
+
+    /// Restore the note ID to revision REV, the current content is saved as a revision first
+    Restore { id: String, rev: usize },
+
+    /// List, restore or empty deleted notes
+    Trash {
+        #[command(subcommand)]
+        action: TrashCommand,
+    },
+
+    /// Find files which are not notes and broken notes, including the quarantined ones
+    Doctor {
+        /// Repair what can be repaired, like restoring a fixed note from the quarantine
+        #[arg(long)]
+        fix: bool,
+    },
 }
 
-fn main() -> Result<(), MemoError> {
-    let cli = Cli::parse();
+#[derive(Subcommand)]
+enum TrashCommand {
+    /// List deleted notes, the latest first
+    Ls,
 
-    let level = match cli.verbose {
-        1 => LevelFilter::DEBUG,
-        2 => LevelFilter::TRACE,
-        _ => LevelFilter::INFO,
-    };
+    /// Restore the deleted note ID, the latest one if it was deleted several times
+    Restore { id: String },
 
-    if let Some(log) = cli.log.as_deref() {
-        JloggerBuilder::new()
-            .log_console(false)
-            .log_file(Some((log, false)))
-            .max_level(level)
-            .log_time(LogTimeFormat::TimeNone)
-            .build();
-    } else {
-        JloggerBuilder::new()
-            .max_level(level)
-            .log_time(LogTimeFormat::TimeNone)
-            .build();
+    /// Delete notes in the trash permanently
+    Empty {
+        /// Only the notes deleted more than AGE ago, like 30d, 12h or 2w
+        #[arg(long, value_name = "AGE")]
+        older_than: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum TagCommand {
+    /// Rename tag OLD to NEW
+    Rename { old: String, new: String },
+
+    /// Replace the given tags with the one of "--into"
+    Merge {
+        #[arg(required = true)]
+        tags: Vec<String>,
+
+        /// Tag to merge into
+        #[arg(long)]
+        into: String,
+    },
+
+    /// Remove a tag from all notes
+    Rm { tag: String },
+}
+
+impl SearchArgs {
+    /// Whether any option or keyword is given.
+    fn is_given(&self) -> bool {
+        self.ignore_case
+            || self.regex
+            || self.glob
+            || self.fuzzy
+            || self.max_distance.is_some()
+            || self.word
+            || self.tag.is_some()
+            || self.since.is_some()
+            || self.until.is_some()
+            || self.sort != SortKey::Score
+            || self.args.is_some()
     }
 
-    if cli.add_text_memo || cli.add_html_memo {
-        Memo::create(cli.path.as_deref(), cli.add_html_memo)?;
-        return Ok(());
+    fn condition(&self) -> MatchCondition {
+        MatchCondition {
+            ignore_case: self.ignore_case,
+            match_word: self.word,
+            mode: if self.regex {
+                MatchMode::Regex
+            } else if self.glob {
+                MatchMode::Glob
+            } else if self.fuzzy {
+                MatchMode::Fuzzy
+            } else {
+                MatchMode::Literal
+            },
+            max_distance: self.max_distance,
+        }
     }
 
-    let condition = MatchCondition {
-        ignore_case: cli.ignore_case,
-        match_word: cli.word,
-    };
-    let memo = Memo::load(cli.path.as_deref())?;
-    if memo.is_empty() {
-        jinfo!("No memo.");
-        return Ok(());
+    fn range(&self) -> Result<Option<DateRange>, MemoError> {
+        let mut range = None;
+        if let Some(since) = &self.since {
+            range = Some(DateRange::since(since)?);
+        }
+
+        if let Some(until) = &self.until {
+            let until = DateRange::until(until)?;
+            range = Some(range.map(|r| r.and(until)).unwrap_or(until));
+        }
+
+        Ok(range)
+    }
+}
+
+/// Notes found by a search, ordered as requested.
+struct SearchResult<'a> {
+    title: String,
+    entries: Vec<(&'a MemoEntry, f64)>,
+    show_score: bool,
+}
+
+/// The note `keys` refers to if it is the identifier of exactly one note.
+fn find_by_id<'a>(memo: &'a Memo, keys: &str) -> Option<&'a MemoEntry> {
+    let keys = keys.trim();
+    if keys.len() < SHORT_ID_LEN || !keys.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    match memo
+        .find_else(|e| e.match_id(keys))
+        .ok()?
+        .entries()
+        .as_slice()
+    {
+        [entry] => Some(entry),
+        _ => None,
+    }
+}
+
+fn search<'a>(memo: &'a Memo, args: &SearchArgs) -> Result<SearchResult<'a>, MemoError> {
+    if let Some(entry) = args.args.as_deref().and_then(|keys| find_by_id(memo, keys)) {
+        jdebug!("{} refers to {}", entry.id(), entry.full_path());
+        return Ok(SearchResult {
+            title: format!("Memo {}", entry.short_id()),
+            entries: vec![(entry, 0.0)],
+            show_score: false,
+        });
     }
 
-    let mut result = String::new();
-    let mut h1 = "Memo".to_string();
-    let tag_entries = if let Some(tag) = &cli.tag {
+    let condition = args.condition();
+    let range = args.range()?;
+
+    let mut title = "Memo".to_string();
+    let tag_entries = if let Some(tag) = &args.tag {
         let tag = tag.trim();
-        h1 = format!("Result for tag `{tag}`");
+        title = format!("Result for tag `{tag}`");
         memo.find(Some((tag, true, condition)))?
     } else {
         memo.new_search()
     };
 
-    let args_entries = if let Some(keys) = &cli.args {
-        let mut current = 0_usize;
-        let mut search_queue = VecDeque::new();
-        let mut op_queue = VecDeque::new();
-        let keys = keys.trim_matches('+').trim_matches('-').trim_matches('*');
-
-        loop {
-            jdebug!(current = current, keys_len = keys.len());
-            if current >= keys.len() {
-                break;
-            }
+    let query = args
+        .args
+        .as_deref()
+        .map(|keys| Query::parse(keys, condition))
+        .transpose()?;
 
-            let target = &keys[current..];
-            let mut processed = false;
-            for (pos, a) in target.as_bytes().iter().enumerate() {
-                match a {
-                    b'+' => {
-                        let key = target[..pos].trim();
-
-                        search_queue.push_back(memo.find(Some((key, false, condition)))?);
-                        jdebug!("+ push for {key}\n{:?}", search_queue);
-                        op_queue.push_back(Op::Add);
-                        current += pos + 1;
-                        processed = true;
-                        break;
-                    }
-                    b'-' => {
-                        let key = target[..pos].trim();
-                        search_queue.push_back(memo.find(Some((key, false, condition)))?);
-                        jdebug!("- push for {key}\n{:?}", search_queue);
-                        op_queue.push_back(Op::Sub);
-                        current += pos + 1;
-                        processed = true;
-                        break;
-                    }
-                    b'*' => {
-                        let key = target[..pos].trim();
-                        search_queue.push_back(memo.find(Some((key, false, condition)))?);
-                        jdebug!("* push for {key}\n{:?}", search_queue);
-                        op_queue.push_back(Op::Mul);
-                        current += pos + 1;
-                        processed = true;
-                        break;
-                    }
-                    _ => {}
-                }
-            }
-
-            if !processed {
-                let key = target.trim();
-                search_queue.push_back(memo.find(Some((key, false, condition)))?);
-                jdebug!("push for {target}\n{:?}", search_queue);
-                current += target.len();
-            }
-        }
-
-        if !search_queue.is_empty() {
-            let mut search = search_queue.pop_front().unwrap();
-
-            while let Some(op) = op_queue.pop_front() {
-                let new = search_queue.pop_front().unwrap();
-                jdebug!("search before:\n{:?}", search);
-                jdebug!("new:\n{:?}", new);
-                match op {
-                    Op::Add => search = (search + new)?,
-                    Op::Sub => search = (search - new)?,
-                    Op::Mul => search = (search * new)?,
-                    Op::Nop => {}
-                }
-                jdebug!("search after:\n{:?}", search);
-            }
-            search
-        } else {
-            memo.new_search()
-        }
+    let args_entries = if let Some(query) = &query {
+        query.eval(memo, condition)?
     } else {
         memo.new_search()
     };
 
-    let entries = if cli.tag.is_some() {
-        if cli.args.is_some() {
+    let entries = if args.tag.is_some() {
+        if query.is_some() {
             (tag_entries * args_entries)?
         } else {
             tag_entries
         }
-    } else if cli.args.is_some() {
+    } else if query.is_some() {
         args_entries
     } else {
         memo.find(None)?
     };
 
-    if cli.delete {
-        if !entries.is_empty() {
-            for (i, entry) in entries.entries().into_iter().enumerate() {
-                eprintln!(" {:<4} {}", i + 1, Html::clear_html_tags(entry.title()));
+    let entries = if let Some(range) = range {
+        jdebug!("Filter by creation time {range}");
+        entries.find_else(|e| e.match_created(&range))?
+    } else {
+        entries
+    };
+
+    let scorer = Scorer::new(
+        query.as_ref(),
+        args.tag.as_deref().map(str::trim),
+        condition,
+    )?;
+
+    Ok(SearchResult {
+        title,
+        entries: scorer.rank(&entries, args.sort),
+        show_score: query.is_some() || args.tag.is_some(),
+    })
+}
+
+/// Ask which notes in `result` to pick, `all` tells whether "yes" selects all of them.
+fn select<'a>(
+    result: &SearchResult<'a>,
+    prompt: &str,
+    all: bool,
+) -> Result<Vec<&'a MemoEntry>, MemoError> {
+    for (i, (entry, _)) in result.entries.iter().enumerate() {
+        eprintln!(
+            " {:<4} {}  {}",
+            i + 1,
+            entry.short_id(),
+            Html::clear_html_tags(entry.title())
+        );
+    }
+
+    if all {
+        eprint!("\n{prompt} ( [y|yes|Y|Yes]=all | Index=like 1,2,3-5... | Other=cancel)?");
+    } else {
+        eprint!("\n{prompt} ( Index=like 1 | Other=cancel)?");
+    }
+
+    let mut selection = String::new();
+    io::stdin().read_line(&mut selection).map_err(|e| {
+        Report::new(MemoError::IOError).attach_printable(format!("Failed to read stdin: {e}"))
+    })?;
+
+    if selection.is_empty() {
+        return Ok(vec![]);
+    }
+
+    match selection.as_str().trim() {
+        "y" | "yes" | "Y" | "Yes" if all => Ok(result.entries.iter().map(|&(e, _)| e).collect()),
+        patten => {
+            let len = result.entries.len();
+            let mut picked = vec![false; len];
+            let patten = patten.replace(' ', "");
+            let re = Regex::new("(([0-9]+-[0-9]+)|([0-9]+))").unwrap();
+            let parse = |n: &str| {
+                n.parse::<usize>().map_err(|e| {
+                    Report::new(MemoError::InvalidValue)
+                        .attach_printable(format!("Invalid index `{n}`: {e}"))
+                })
+            };
+
+            for it in re.find_iter(&patten) {
+                let number = it.as_str();
+                let (a, b) = match number.split_once('-') {
+                    Some((a, b)) => (parse(a)?, parse(b)?),
+                    None => (parse(number)?, parse(number)?),
+                };
+
+                // Indexes are from 1, the ones out of the list are ignored.
+                for i in a.min(b).max(1)..=a.max(b).min(len) {
+                    picked[i - 1] = true;
+                }
             }
 
-            eprint!(
-                "\nWhich to delete ( [y|yes|Y|Yes]=all | Index=like 1,2,3-5... | Other=cancel)?"
-            );
-            let mut selection = String::new();
-            io::stdin().read_line(&mut selection).unwrap();
+            Ok(result
+                .entries
+                .iter()
+                .zip(picked)
+                .filter(|(_, p)| *p)
+                .map(|(&(e, _), _)| e)
+                .collect())
+        }
+    }
+}
+
+/// Move notes selected from `result` to the trash.
+fn delete(memo: &Memo, result: &SearchResult) -> Result<(), MemoError> {
+    if result.entries.is_empty() {
+        jinfo!("No memo to delete.");
+        return Ok(());
+    }
+
+    for entry in select(result, "Which to delete", true)? {
+        jdebug!("Remove {}\n({})", entry.full_path(), entry.title());
+        match Trash::put(memo.root(), entry.full_path(), "rm") {
+            Ok(_) => jinfo!(
+                "{} is moved to the trash, undo with \"memo trash restore {}\".",
+                Html::clear_html_tags(entry.title()),
+                entry.short_id()
+            ),
+            Err(e) => jerror!("Failed to remove {}: {:?}", entry.full_path(), e),
+        }
+    }
+
+    Ok(())
+}
+
+fn browse(memo: &Memo, result: &SearchResult) -> Result<(), MemoError> {
+    if result.entries.is_empty() {
+        jinfo!("No memo.");
+        return Ok(());
+    }
+
+    let mut output_html = String::new();
+    output_html.push_str(&Html::h1(&format!(
+        "{} ({})",
+        result.title,
+        result.entries.len()
+    )));
+
+    // Markdown notes are linked to the html rendered from them.
+    let rendered = format!("{}/rendered", memo.root());
+    let mut links = vec![];
+    for &(a, _) in &result.entries {
+        if a.kind() != "md" {
+            links.push(a.full_path().to_owned());
+            continue;
+        }
 
-            if selection.is_empty() {
-                return Ok(());
+        let page = format!("{rendered}/{}.html", a.file_name().trim_end_matches(".md"));
+        fs::create_dir_all(&rendered)
+            .and_then(|_| fs::write(&page, Markdown::page(a.title(), a.body())))
+            .map_err(|e| {
+                Report::new(MemoError::IOError)
+                    .attach_printable(format!("Failed to write {page}: {e}"))
+            })?;
+        links.push(page);
+    }
+
+    let entries: Vec<String> = result
+        .entries
+        .iter()
+        .zip(&links)
+        .map(|(&(a, score), link)| {
+            let fix = Html::clear_html_tags(a.title());
+            let mut s = Html::link(&fix, link);
+            s.push('\n');
+            s.push_str(&format!("id: {}", a.short_id()));
+            s.push('\n');
+            s.push_str(&format!("tags: {}", a.tags()));
+            s.push('\n');
+            s.push_str(&format!("created at: {}", a.create_time()));
+            if result.show_score {
+                s.push('\n');
+                s.push_str(&format!("score: {score:.2}"));
             }
+            s
+        })
+        .collect();
+
+    output_html.push_str(&Html::list(entries.iter().map(|a| a.as_str()).collect()));
+
+    let output = format!("{}/index.html", memo.root());
+
+    let _ = fs::remove_file(&output);
+    fs::write(&output, output_html).map_err(|e| {
+        Report::new(MemoError::IOError)
+            .attach_printable(format!("Failed to write result to {output}: {e}"))
+    })?;
+
+    let browser = env::var("BROWSER").unwrap_or("w3m".to_owned());
+    let mut args = vec![];
+    if &browser == "w3m" {
+        args.push("-num");
+        args.push("-T");
+        args.push("text/html");
+    }
+
+    args.push(&output);
+    let mut handle = Command::new(browser).args(args).spawn().map_err(|e| {
+        Report::new(MemoError::Unexpected).attach_printable(format!("Failed to execute w3m: {e}"))
+    })?;
 
-            match selection.as_str().trim() {
-                "y" | "yes" | "Y" | "Yes" => {
-                    for entry in entries.entries().into_iter() {
-                        jdebug!("Remove {}\n({})", entry.full_path(), entry.title());
-                        if let Err(e) = fs::remove_file(entry.full_path()) {
-                            jerror!("Failed to remove {}: {:?}", entry.full_path(), e);
-                        }
+    handle.wait().map_err(|e| {
+        Report::new(MemoError::Unexpected).attach_printable(format!("w3m failed: {e}"))
+    })?;
+
+    Ok(())
+}
+
+/// Display `result` in `mode`, or `default` if not specified and stdout is a terminal.
+fn display(
+    memo: &Memo,
+    result: &SearchResult,
+    mode: Option<OutputMode>,
+    default: OutputMode,
+    with_body: bool,
+) -> Result<(), MemoError> {
+    let score = |s: f64| if result.show_score { Some(s) } else { None };
+
+    let mode = mode.unwrap_or(if io::stdout().is_terminal() {
+        default
+    } else {
+        OutputMode::Plain
+    });
+
+    match mode {
+        OutputMode::Browser => browse(memo, result),
+        OutputMode::Tty => {
+            if result.entries.is_empty() {
+                jinfo!("No memo.");
+            } else {
+                print!(
+                    "{}",
+                    Tty::table(&result.entries, result.show_score, Tty::color_enabled())
+                );
+            }
+            Ok(())
+        }
+        OutputMode::Plain => {
+            print!("{}", Tty::plain(&result.entries, result.show_score));
+            Ok(())
+        }
+        OutputMode::Json => {
+            let entries: Vec<String> = result
+                .entries
+                .iter()
+                .map(|&(e, s)| Json::entry(e, score(s), with_body))
+                .collect();
+
+            if entries.is_empty() {
+                println!("[]");
+            } else {
+                println!("[\n  {}\n]", entries.join(",\n  "));
+            }
+            Ok(())
+        }
+        OutputMode::Ndjson => {
+            for &(e, s) in &result.entries {
+                println!("{}", Json::entry(e, score(s), with_body));
+            }
+            Ok(())
+        }
+    }
+}
+
+/// The note `id`, a prefix of the ID is accepted.
+fn note_by_id<'a>(memo: &'a Memo, id: &str) -> Result<&'a MemoEntry, MemoError> {
+    let found = memo.find_else(|e| e.match_id(id))?.entries();
+    match found.as_slice() {
+        [entry] => Ok(entry),
+        [] => {
+            Err(Report::new(MemoError::InvalidValue)).attach_printable(format!("No note `{id}`."))
+        }
+        _ => {
+            let ids: Vec<&str> = found.iter().map(|e| e.short_id()).collect();
+            Err(Report::new(MemoError::InvalidValue))
+                .attach_printable(format!("ID `{id}` is ambiguous: {}", ids.join(" ")))
+        }
+    }
+}
+
+fn history_log(memo: &Memo, id: &str, mode: Option<OutputMode>) -> Result<(), MemoError> {
+    let entry = note_by_id(memo, id)?;
+    let revs = History::list(memo.root(), entry.id());
+
+    let mode = mode.unwrap_or(if io::stdout().is_terminal() {
+        OutputMode::Tty
+    } else {
+        OutputMode::Plain
+    });
+
+    match mode {
+        OutputMode::Browser | OutputMode::Tty => {
+            print!("{}", Tty::log(entry, &revs, Tty::color_enabled()))
+        }
+        OutputMode::Plain => print!("{}", Tty::plain_log(&revs)),
+        OutputMode::Json => {
+            let items: Vec<String> = revs.iter().rev().map(Json::revision).collect();
+            if items.is_empty() {
+                println!("[]");
+            } else {
+                println!("[\n  {}\n]", items.join(",\n  "));
+            }
+        }
+        OutputMode::Ndjson => {
+            for r in revs.iter().rev() {
+                println!("{}", Json::revision(r));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn history_diff(memo: &Memo, id: &str, rev: Option<usize>) -> Result<(), MemoError> {
+    let entry = note_by_id(memo, id)?;
+    let rev = History::get(memo.root(), entry.id(), rev)?;
+    let old = rev.content()?;
+    let new = fs::read_to_string(entry.full_path()).map_err(|e| {
+        Report::new(MemoError::IOError)
+            .attach_printable(format!("Failed to read {}: {e}", entry.full_path()))
+    })?;
+
+    if old == new {
+        jinfo!("No change since revision {}.", rev.number);
+    } else {
+        let color = Tty::color_enabled() && io::stdout().is_terminal();
+        print!(
+            "{}",
+            Tty::diff(&rev.path, entry.full_path(), &old, &new, color)
+        );
+    }
+
+    Ok(())
+}
+
+fn history_restore(memo: &Memo, id: &str, rev: usize) -> Result<(), MemoError> {
+    let entry = note_by_id(memo, id)?;
+    let rev = History::get(memo.root(), entry.id(), Some(rev))?;
+    let saved = History::restore(memo.root(), entry.full_path(), &rev)?;
+
+    jinfo!(
+        "{} is restored to revision {}, the replaced content is revision {}.",
+        Html::clear_html_tags(entry.title()),
+        rev.number,
+        saved.number
+    );
+    Ok(())
+}
+
+fn trash(
+    path: Option<&str>,
+    action: &TrashCommand,
+    mode: Option<OutputMode>,
+) -> Result<(), MemoError> {
+    let (root, _) = Memo::setup_root(path)?;
+
+    match action {
+        TrashCommand::Ls => {
+            let list = Trash::list(&root);
+            let mode = mode.unwrap_or(if io::stdout().is_terminal() {
+                OutputMode::Tty
+            } else {
+                OutputMode::Plain
+            });
+
+            match mode {
+                OutputMode::Browser | OutputMode::Tty => {
+                    if list.is_empty() {
+                        jinfo!("Trash is empty.");
+                    } else {
+                        print!("{}", Tty::trash(&list, Tty::color_enabled()));
                     }
                 }
-                patten => {
-                    let mut index: Vec<usize> = vec![];
-                    let patten = patten.replace(' ', "");
-                    let re = Regex::new("(([0-9]+-[0-9]+)|([0-9]+))").unwrap();
-
-                    for it in re.find_iter(&patten) {
-                        let number = it.as_str();
-
-                        if number.contains('-') {
-                            if let Some(pos) = number.as_bytes().iter().position(|&a| a == b'-') {
-                                let (a, b) = number.split_at(pos);
-                                let a = a.parse::<usize>().unwrap();
-                                let b = b.trim_matches('-').parse::<usize>().unwrap();
-
-                                let mut start = a;
-                                let mut end = b;
-
-                                if a > b {
-                                    start = b;
-                                    end = a;
-                                }
-
-                                for i in start..=end {
-                                    if !index.iter().any(|&a| a == i) {
-                                        index.push(i);
-                                    }
-                                }
-                            }
-                        } else {
-                            index.push(number.parse::<usize>().unwrap());
-                        }
+                OutputMode::Plain => print!("{}", Tty::plain_trash(&list)),
+                OutputMode::Json => {
+                    let items: Vec<String> = list.iter().map(Json::trashed).collect();
+                    if items.is_empty() {
+                        println!("[]");
+                    } else {
+                        println!("[\n  {}\n]", items.join(",\n  "));
                     }
-
-                    for (i, entry) in entries.entries().into_iter().enumerate() {
-                        if index.iter().any(|&a| a == i + 1) {
-                            jdebug!("Remove {}\n({})", entry.full_path(), entry.title());
-                            if let Err(e) = fs::remove_file(entry.full_path()) {
-                                jerror!("Failed to remove {}: {:?}", entry.full_path(), e);
-                            }
-                        }
+                }
+                OutputMode::Ndjson => {
+                    for t in &list {
+                        println!("{}", Json::trashed(t));
                     }
                 }
             }
-        } else {
-            jinfo!("No memo to delete.")
         }
+        TrashCommand::Restore { id } => {
+            let t = Trash::find(&root, id)?;
+            let to = Trash::restore(&root, &t)?;
+            jinfo!("{} is restored to {to}.", Html::clear_html_tags(&t.title));
+        }
+        TrashCommand::Empty { older_than } => {
+            let n = Trash::empty(&root, older_than.as_deref())?;
+            jinfo!("{n} notes are deleted permanently.");
+        }
+    }
+
+    Ok(())
+}
+
+/// Report problems of the files under the root path and repair them if `fix` is set.
+fn doctor(path: Option<&str>, fix: bool) -> Result<(), MemoError> {
+    let (root, _) = Memo::setup_root(path)?;
+    let findings = Doctor::inspect(&root);
+    if findings.is_empty() {
+        jinfo!("No problem found.");
         return Ok(());
     }
 
-    if !entries.is_empty() {
-        result.push_str(&Html::h1(&format!("{h1} ({})", entries.entries().len())));
+    let mut fixed = 0;
+    for f in &findings {
+        println!("{:<12} {}: {}", f.problem.to_string(), f.path, f.reason);
 
-        let entries: Vec<String> = entries
-            .entries()
-            .iter()
-            .map(|&a| {
-                let fix = Html::clear_html_tags(a.title());
-                let mut s = Html::link(&fix, a.full_path());
-                s.push('\n');
-                s.push_str(&format!("tags: {}", a.tags()));
-                s.push('\n');
-                s.push_str(&format!("created at: {}", a.create_time()));
-                s
-            })
-            .collect();
+        match (&f.fix, fix) {
+            (Some(how), false) => println!("{:<12} --fix will {how}", ""),
+            (Some(_), true) => match f.repair(&root) {
+                Ok(to) => {
+                    println!("{:<12} fixed, now {to}", "");
+                    fixed += 1;
+                }
+                Err(e) => jwarn!("Failed to repair {}:\n{:?}", f.path, e),
+            },
+            (None, _) => {}
+        }
+    }
 
-        if !entries.is_empty() {
-            result.push_str(&Html::list(entries.iter().map(|a| a.as_str()).collect()));
+    if fix {
+        jinfo!("{fixed} of {} files repaired.", findings.len());
+    }
 
-            let output = format!("{}/index.html", memo.root());
+    Ok(())
+}
 
-            let _ = fs::remove_file(&output);
-            fs::write(&output, result).map_err(|e| {
+/// Content of a note as text, html and markdown notes are converted.
+fn note_text(entry: &MemoEntry, color: bool) -> Result<String, MemoError> {
+    match entry.kind() {
+        "html" => {
+            let mut text = Html::to_text(entry.title());
+            text.push_str(&Html::to_text(entry.body()));
+            return Ok(text);
+        }
+        "md" => {
+            let md = format!("# {}\n\n{}", entry.title(), entry.body());
+            return Ok(Markdown::to_text(&md, color));
+        }
+        _ => {}
+    }
+
+    fs::read_to_string(entry.full_path()).map_err(|e| {
+        Report::new(MemoError::IOError)
+            .attach_printable(format!("Failed to read {}: {e}", entry.full_path()))
+    })
+}
+
+/// Create a note from the arguments and stdin without the editor.
+///
+/// Without a title, the first line of the body is used as the title like in the editor.
+fn new_note(
+    path: Option<&str>,
+    kind: &str,
+    title: Option<&str>,
+    tags: &[String],
+    body: Option<&str>,
+) -> Result<MemoEntry, MemoError> {
+    let body = match body {
+        Some(b) => b.to_owned(),
+        None if !io::stdin().is_terminal() => {
+            let mut b = String::new();
+            io::stdin().read_to_string(&mut b).map_err(|e| {
                 Report::new(MemoError::IOError)
-                    .attach_printable(format!("Failed to write result to {output}: {e}"))
+                    .attach_printable(format!("Failed to read stdin: {e}"))
             })?;
+            b
+        }
+        None => String::new(),
+    };
 
-            let browser = env::var("BROWSER").unwrap_or("w3m".to_owned());
-            let mut args = vec![];
-            if &browser == "w3m" {
-                args.push("-num");
-                args.push("-T");
-                args.push("text/html");
-            }
+    match title {
+        Some(t) => Memo::write(path, kind, t, tags, &body),
+        None if FrontMatter::parse(&body).is_some() => Memo::write(path, kind, "", tags, &body),
+        None => {
+            let (t, b) = body.split_once('\n').unwrap_or((&body, ""));
+            Memo::write(path, kind, t, tags, b)
+        }
+    }
+}
 
-            args.push(&output);
-            let mut handle = Command::new(browser).args(args).spawn().map_err(|e| {
-                Report::new(MemoError::Unexpected)
-                    .attach_printable(format!("Failed to execute w3m: {e}"))
-            })?;
+fn page(text: &str) -> Result<(), MemoError> {
+    let pager = env::var("PAGER").unwrap_or("less -R".to_owned());
+    let mut args = pager.split_whitespace();
+    let program = args.next().unwrap_or("less");
 
-            handle.wait().map_err(|e| {
-                Report::new(MemoError::Unexpected).attach_printable(format!("w3m failed: {e}"))
-            })?;
-        } else {
+    let mut handle = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .spawn()
+        .map_err(|e| {
+            Report::new(MemoError::Unexpected)
+                .attach_printable(format!("Failed to execute {pager}: {e}"))
+        })?;
+
+    if let Some(mut stdin) = handle.stdin.take() {
+        // The pager may quit before reading everything.
+        let _ = stdin.write_all(text.as_bytes());
+    }
+
+    handle.wait().map_err(|e| {
+        Report::new(MemoError::Unexpected).attach_printable(format!("{pager} failed: {e}"))
+    })?;
+
+    Ok(())
+}
+
+fn show(result: &SearchResult, pager: bool) -> Result<(), MemoError> {
+    if result.entries.is_empty() {
+        jinfo!("No memo.");
+        return Ok(());
+    }
+
+    let color = Tty::color_enabled() && io::stdout().is_terminal();
+    let mut text = String::new();
+    for (i, &(entry, _)) in result.entries.iter().enumerate() {
+        if i > 0 {
+            text.push('\n');
+        }
+
+        text.push_str(&note_text(entry, color)?);
+    }
+
+    if pager {
+        page(&text)
+    } else {
+        print!("{text}");
+        Ok(())
+    }
+}
+
+fn edit(memo: &Memo, result: &SearchResult) -> Result<(), MemoError> {
+    let entry = match result.entries.len() {
+        0 => {
             jinfo!("No memo.");
+            return Ok(());
         }
+        1 => result.entries[0].0,
+        _ => match select(result, "Which to edit", false)?.as_slice() {
+            [entry] => entry,
+            [] => return Ok(()),
+            _ => {
+                return Err(Report::new(MemoError::InvalidValue))
+                    .attach_printable("Only one note can be edited at once.")
+            }
+        },
+    };
+
+    if Memo::edit(memo.root(), entry.full_path())?.is_none() {
+        jinfo!("{} is removed.", Html::clear_html_tags(entry.title()));
+    }
+
+    Ok(())
+}
+
+fn tags(memo: &Memo, tree: bool, mode: Option<OutputMode>) -> Result<(), MemoError> {
+    let stats = TagStat::collect(&memo.find(None)?.entries(), tree);
+
+    let mode = mode.unwrap_or(if io::stdout().is_terminal() {
+        OutputMode::Tty
     } else {
-        jinfo!("No memo.");
+        OutputMode::Plain
+    });
+
+    match mode {
+        OutputMode::Browser | OutputMode::Tty => {
+            if stats.is_empty() {
+                jinfo!("No tag.");
+            } else {
+                print!("{}", Tty::tags(&stats, tree, Tty::color_enabled()));
+            }
+        }
+        OutputMode::Plain => print!("{}", Tty::plain_tags(&stats)),
+        OutputMode::Json => {
+            let items: Vec<String> = if tree {
+                Json::tag_tree(&stats, &mut 0, 0)
+            } else {
+                stats.iter().map(|s| Json::tag(s, None)).collect()
+            };
+
+            if items.is_empty() {
+                println!("[]");
+            } else {
+                println!("[\n  {}\n]", items.join(",\n  "));
+            }
+        }
+        OutputMode::Ndjson => {
+            for s in &stats {
+                println!("{}", Json::tag(s, None));
+            }
+        }
     }
 
     Ok(())
 }
+
+fn retag(memo: &Memo, action: &TagCommand, dry_run: bool) -> Result<(), MemoError> {
+    let retag = match action {
+        TagCommand::Rename { old, new } => Retag::rename(old, new)?,
+        TagCommand::Merge { tags, into } => Retag::merge(tags, into)?,
+        TagCommand::Rm { tag } => Retag::remove(tag)?,
+    };
+
+    let changes = retag.plan(&memo.find(None)?.entries())?;
+    if changes.is_empty() {
+        jinfo!("No note to change.");
+        return Ok(());
+    }
+
+    if dry_run {
+        let color = Tty::color_enabled() && io::stdout().is_terminal();
+        for c in &changes {
+            print!("{}", c.diff(color));
+        }
+        jinfo!("{} notes would be changed.", changes.len());
+        return Ok(());
+    }
+
+    let mut n = 0;
+    for c in &changes {
+        match c.apply() {
+            Ok(()) => {
+                jdebug!("{}: {} => {}", c.path, c.old, c.new);
+                n += 1;
+            }
+            Err(e) => jwarn!("{e:?}"),
+        }
+    }
+
+    jinfo!("{n} notes changed.");
+    Ok(())
+}
+
+/// Parse the command line, a keyword which is also a command name like "memo log" is searched
+/// if it can not be parsed as the command.
+fn parse_cli() -> Cli {
+    let args: Vec<String> = env::args().collect();
+    let cli = Cli::try_parse_from(&args).unwrap_or_else(|e| {
+        let command = Cli::command();
+        let word = args
+            .iter()
+            .skip(1)
+            .position(|a| command.find_subcommand(a).is_some());
+
+        // "memo -I log" is parsed as "memo search -I log", and "memo -d log" as "memo -d -- log".
+        word.and_then(|i| {
+            [(1, "search"), (i + 1, "--")]
+                .into_iter()
+                .find_map(|(at, arg)| {
+                    let mut args = args.clone();
+                    args.insert(at, arg.to_owned());
+                    Cli::try_parse_from(args).ok()
+                })
+        })
+        .unwrap_or_else(|| e.exit())
+    });
+
+    // Options of the search before a command would be ignored by the command.
+    if cli.command.is_some()
+        && (cli.search.is_given() || cli.delete || cli.add_text_memo || cli.add_html_memo)
+    {
+        Cli::command()
+            .bin_name("memo")
+            .error(
+                ErrorKind::ArgumentConflict,
+                "options of the search must follow the command, like \"memo ls -I rust\"",
+            )
+            .exit();
+    }
+
+    cli
+}
+
+fn main() -> Result<(), MemoError> {
+    let cli = parse_cli();
+
+    let level = match cli.verbose {
+        1 => LevelFilter::DEBUG,
+        2 => LevelFilter::TRACE,
+        _ => LevelFilter::INFO,
+    };
+
+    if let Some(log) = cli.log.as_deref() {
+        JloggerBuilder::new()
+            .log_console(false)
+            .log_file(Some((log, false)))
+            .max_level(level)
+            .log_time(LogTimeFormat::TimeNone)
+            .build();
+    } else {
+        JloggerBuilder::new()
+            .max_level(level)
+            .log_time(LogTimeFormat::TimeNone)
+            .build();
+    }
+
+    let path = cli.path.as_deref();
+
+    match &cli.command {
+        Some(Commands::New {
+            html,
+            md,
+            title,
+            tags,
+            body,
+            template,
+        }) => {
+            let kind = match (html, md) {
+                (true, _) => "html",
+                (_, true) => "md",
+                _ => "txt",
+            };
+
+            if template.is_some()
+                || (title.is_none()
+                    && tags.is_empty()
+                    && body.is_none()
+                    && io::stdin().is_terminal())
+            {
+                Memo::create(path, kind, template.as_deref())?;
+            } else {
+                let entry = new_note(path, kind, title.as_deref(), tags, body.as_deref())?;
+                println!("{}", entry.short_id());
+            }
+            return Ok(());
+        }
+        Some(Commands::Doctor { fix }) => return doctor(path, *fix),
+        Some(Commands::Trash { action }) => return trash(path, action, cli.output),
+        Some(Commands::Reindex) => {
+            let mut memo = Memo::load(path)?;
+            let n = memo.reindex()?;
+            jinfo!("{n} notes indexed.");
+            return Ok(());
+        }
+        None if cli.add_text_memo || cli.add_html_memo => {
+            let kind = if cli.add_html_memo { "html" } else { "txt" };
+            Memo::create(path, kind, None)?;
+            return Ok(());
+        }
+        _ => {}
+    }
+
+    let memo = Memo::load(path)?;
+    if memo.is_empty() {
+        jinfo!("No memo.");
+        return Ok(());
+    }
+
+    match &cli.command {
+        Some(Commands::Search(args)) => display(
+            &memo,
+            &search(&memo, args)?,
+            cli.output,
+            OutputMode::Browser,
+            cli.with_body,
+        ),
+        Some(Commands::Show {
+            search: args,
+            pager,
+        }) => show(&search(&memo, args)?, *pager),
+        Some(Commands::Edit(args)) => edit(&memo, &search(&memo, args)?),
+        Some(Commands::Rm(args)) => delete(&memo, &search(&memo, args)?),
+        Some(Commands::Ls(args)) => display(
+            &memo,
+            &search(&memo, args)?,
+            cli.output,
+            OutputMode::Tty,
+            cli.with_body,
+        ),
+        Some(Commands::Tags { tree }) => tags(&memo, *tree, cli.output),
+        Some(Commands::Tag { action, dry_run }) => retag(&memo, action, *dry_run),
+        Some(Commands::Log { id }) => history_log(&memo, id, cli.output),
+        Some(Commands::Diff { id, rev }) => history_diff(&memo, id, *rev),
+        Some(Commands::Restore { id, rev }) => history_restore(&memo, id, *rev),
+        Some(Commands::New { .. })
+        | Some(Commands::Reindex)
+        | Some(Commands::Doctor { .. })
+        | Some(Commands::Trash { .. }) => Ok(()),
+        None if cli.delete => delete(&memo, &search(&memo, &cli.search)?),
+        None => display(
+            &memo,
+            &search(&memo, &cli.search)?,
+            cli.output,
+            OutputMode::Browser,
+            cli.with_body,
+        ),
+    }
+}
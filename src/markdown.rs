@@ -0,0 +1,288 @@
+#[allow(unused)]
+use {
+    super::html::Html,
+    pulldown_cmark::{html, Event, HeadingLevel, LinkType, Options, Parser, Tag},
+};
+
+pub struct Markdown;
+
+/// Terminal text being built from markdown.
+struct Text {
+    out: String,
+    color: bool,
+    /// Escape sequences of the styles in effect, innermost last.
+    styles: Vec<&'static str>,
+    /// Prefixes of the nested blocks, "> " of a quote or the indent of a list item.
+    prefix: Vec<String>,
+    /// `None` for a bullet list and the next number for a numbered one.
+    lists: Vec<Option<u64>>,
+    links: Vec<String>,
+}
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const ITALIC: &str = "\x1b[3m";
+const UNDERLINE: &str = "\x1b[4m";
+const STRIKE: &str = "\x1b[9m";
+const CODE: &str = "\x1b[36m";
+
+impl Text {
+    fn at_line_start(&self) -> bool {
+        self.out.is_empty() || self.out.ends_with('\n')
+    }
+
+    fn apply_styles(&mut self) {
+        if self.color && !self.styles.is_empty() {
+            self.out.push_str(&self.styles.concat());
+        }
+    }
+
+    fn push_style(&mut self, style: &'static str) {
+        self.styles.push(style);
+        if self.color {
+            self.out.push_str(style);
+        }
+    }
+
+    fn pop_style(&mut self) {
+        self.styles.pop();
+        if self.color {
+            self.out.push_str(RESET);
+            self.apply_styles();
+        }
+    }
+
+    fn push(&mut self, s: &str) {
+        for (i, line) in s.split('\n').enumerate() {
+            if i > 0 {
+                self.newline();
+            }
+
+            if line.is_empty() {
+                continue;
+            }
+
+            if self.at_line_start() {
+                self.out.push_str(&self.prefix.concat());
+                self.apply_styles();
+            }
+            self.out.push_str(line);
+        }
+    }
+
+    fn newline(&mut self) {
+        if self.color && !self.styles.is_empty() {
+            self.out.push_str(RESET);
+        }
+
+        while self.out.ends_with(' ') {
+            self.out.pop();
+        }
+        self.out.push('\n');
+    }
+
+    /// End the current line if any.
+    fn line(&mut self) {
+        if !self.at_line_start() {
+            self.newline();
+        }
+    }
+
+    /// End the current block with a blank line.
+    fn blank(&mut self) {
+        self.line();
+        if !self.out.is_empty() && !self.out.ends_with("\n\n") {
+            self.out.push('\n');
+        }
+    }
+
+    fn start(&mut self, tag: Tag) {
+        match tag {
+            Tag::Heading(level, _, _) => {
+                self.line();
+                self.push_style(BOLD);
+                if level == HeadingLevel::H1 {
+                    self.push_style(UNDERLINE);
+                }
+            }
+            Tag::BlockQuote => {
+                self.line();
+                self.prefix.push("> ".to_owned());
+            }
+            Tag::CodeBlock(_) => {
+                self.line();
+                self.prefix.push("    ".to_owned());
+                self.push_style(CODE);
+            }
+            Tag::List(first) => {
+                self.line();
+                self.lists.push(first);
+            }
+            Tag::Item => {
+                self.line();
+                let marker = match self.lists.last_mut() {
+                    Some(Some(n)) => {
+                        *n += 1;
+                        format!("{}. ", *n - 1)
+                    }
+                    _ => "- ".to_owned(),
+                };
+                self.push(&marker);
+                self.prefix.push(" ".repeat(marker.len()));
+            }
+            Tag::FootnoteDefinition(name) => {
+                self.line();
+                self.push(&format!("[^{name}]: "));
+            }
+            Tag::TableHead => self.push_style(BOLD),
+            Tag::TableCell if !self.at_line_start() => self.push(" | "),
+            Tag::Emphasis => self.push_style(ITALIC),
+            Tag::Strong => self.push_style(BOLD),
+            Tag::Strikethrough => self.push_style(STRIKE),
+            Tag::Link(kind, url, _) => {
+                self.push_style(UNDERLINE);
+                // The text of an autolink is the target itself.
+                if matches!(kind, LinkType::Autolink | LinkType::Email) {
+                    self.links.push(String::new());
+                } else {
+                    self.links.push(url.to_string());
+                }
+            }
+            Tag::Image(_, url, _) => {
+                self.push("[image: ");
+                self.links.push(url.to_string());
+            }
+            _ => {}
+        }
+    }
+
+    fn end(&mut self, tag: Tag) {
+        match tag {
+            Tag::Heading(level, _, _) => {
+                if level == HeadingLevel::H1 {
+                    self.pop_style();
+                }
+                self.pop_style();
+                self.blank();
+            }
+            Tag::Paragraph | Tag::Table(_) => self.blank(),
+            Tag::BlockQuote => {
+                self.prefix.pop();
+                self.blank();
+            }
+            Tag::CodeBlock(_) => {
+                self.pop_style();
+                self.prefix.pop();
+                self.blank();
+            }
+            Tag::List(_) => {
+                self.lists.pop();
+                if self.lists.is_empty() {
+                    self.blank();
+                }
+            }
+            Tag::Item => {
+                self.line();
+                self.prefix.pop();
+            }
+            Tag::FootnoteDefinition(_) | Tag::TableRow => self.line(),
+            Tag::TableHead => {
+                self.pop_style();
+                self.line();
+            }
+            Tag::Emphasis | Tag::Strong | Tag::Strikethrough => self.pop_style(),
+            Tag::Link(..) => {
+                self.pop_style();
+                if let Some(url) = self.links.pop().filter(|a| !a.is_empty()) {
+                    self.push(&format!(" <{url}>"));
+                }
+            }
+            Tag::Image(..) => {
+                self.push("]");
+                if let Some(url) = self.links.pop().filter(|a| !a.is_empty()) {
+                    self.push(&format!(" <{url}>"));
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Markdown {
+    fn options() -> Options {
+        Options::ENABLE_TABLES
+            | Options::ENABLE_STRIKETHROUGH
+            | Options::ENABLE_TASKLISTS
+            | Options::ENABLE_FOOTNOTES
+    }
+
+    /// Text of a title line without the heading marker, e.g. "Plan" of "## Plan".
+    pub fn heading(line: &str) -> &str {
+        let trimmed = line.trim_start();
+        let rest = trimmed.trim_start_matches('#');
+        let level = trimmed.len() - rest.len();
+
+        if (1..=6).contains(&level) && (rest.is_empty() || rest.starts_with([' ', '\t'])) {
+            rest.trim()
+        } else {
+            line
+        }
+    }
+
+    pub fn to_html(md: &str) -> String {
+        let mut out = String::new();
+        html::push_html(&mut out, Parser::new_ext(md, Markdown::options()));
+        out
+    }
+
+    /// A whole html page of a note, the title is the heading of the page.
+    pub fn page(title: &str, body: &str) -> String {
+        format!(
+            "<html>\n<head><meta charset=\"utf-8\"><title>{}</title></head>\n<body>\n{}</body>\n</html>\n",
+            Html::clear_html_tags(title),
+            Markdown::to_html(&format!("# {title}\n\n{body}"))
+        )
+    }
+
+    /// Convert markdown to text for the terminal.
+    ///
+    /// Headings and emphasis are styled with escape sequences if `color` is set, list items
+    /// are bulleted or numbered, code blocks are indented and links are followed by their
+    /// target.
+    pub fn to_text(md: &str, color: bool) -> String {
+        let mut text = Text {
+            out: String::new(),
+            color,
+            styles: vec![],
+            prefix: vec![],
+            lists: vec![],
+            links: vec![],
+        };
+
+        for event in Parser::new_ext(md, Markdown::options()) {
+            match event {
+                Event::Start(tag) => text.start(tag),
+                Event::End(tag) => text.end(tag),
+                Event::Text(s) => text.push(&s),
+                Event::Code(s) => {
+                    text.push_style(CODE);
+                    text.push(&s);
+                    text.pop_style();
+                }
+                Event::Html(s) => text.push(Html::to_text(&s).trim_end()),
+                Event::FootnoteReference(name) => text.push(&format!("[^{name}]")),
+                Event::SoftBreak | Event::HardBreak => text.newline(),
+                Event::Rule => {
+                    text.line();
+                    text.push("----");
+                    text.blank();
+                }
+                Event::TaskListMarker(done) => text.push(if done { "[x] " } else { "[ ] " }),
+            }
+        }
+
+        let mut out = text.out.trim_end().to_owned();
+        out.push('\n');
+        out
+    }
+}
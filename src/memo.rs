@@ -1,36 +1,323 @@
 #[allow(unused)]
 use {
-    super::error::MemoError,
-    chrono::{Datelike, Local, Timelike},
+    super::{
+        date::DateRange,
+        doctor::Doctor,
+        error::MemoError,
+        front::{FrontMatter, Value},
+        history::History,
+        index::Index,
+        markdown::Markdown,
+        template::Template,
+        trash::Trash,
+    },
+    chrono::{Datelike, Duration, Local, NaiveDate, NaiveDateTime, Timelike},
     clap::Parser,
     error_stack::{Report, Result, ResultExt},
     jlogger_tracing::{
         jdebug, jerror, jinfo, jtrace, jwarn, JloggerBuilder, LevelFilter, LogTimeFormat,
     },
-    regex::Regex,
+    regex::{Match, Regex},
     std::{
+        borrow::Cow,
         boxed::Box,
+        cell::OnceCell,
         env,
         ffi::{CStr, CString},
         fmt::{self, Debug, Display},
         fs::{self, DirEntry},
-        io::{BufRead, BufReader, BufWriter, Read, Write},
+        io::{BufRead, BufReader, BufWriter, ErrorKind, Read, Write},
         mem,
         ops::{Add, Mul, Sub},
         path::{Path, PathBuf},
         process::Command,
         rc::Rc,
-        sync::atomic::{AtomicI32, Ordering},
+        sync::{
+            atomic::{AtomicI32, Ordering},
+            OnceLock,
+        },
     },
+    unicode_normalization::{is_nfc, UnicodeNormalization},
 };
 
+/// How a search key is interpreted.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum MatchMode {
+    /// The key is matched as it is.
+    #[default]
+    Literal,
+    /// The key is a regular expression.
+    Regex,
+    /// The key is a wildcard pattern, `*` matches any characters and `?` matches one character.
+    Glob,
+    /// Words of the key match words within a small edit distance, e.g. "kubernets" matches
+    /// "kubernetes".
+    Fuzzy,
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct MatchCondition {
     pub ignore_case: bool,
     pub match_word: bool,
+    pub mode: MatchMode,
+    /// Maximum edit distance in fuzzy mode, decided by the length of each word if not set.
+    pub max_distance: Option<usize>,
 }
 
-#[derive(Debug)]
+impl MatchCondition {
+    /// Maximum edit distance allowed for `word` in fuzzy mode.
+    pub fn distance_for(&self, word: &str) -> usize {
+        self.max_distance.unwrap_or(match word.chars().count() {
+            0..=2 => 0,
+            3..=5 => 1,
+            _ => 2,
+        })
+    }
+}
+
+/// Levenshtein distance between `a` and `b`, `None` if it is larger than `max`.
+pub fn edit_distance(a: &str, b: &str, max: usize) -> Option<usize> {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+
+    if a.len().abs_diff(b.len()) > max {
+        return None;
+    }
+
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut cur = vec![0; b.len() + 1];
+
+    for i in 1..=a.len() {
+        cur[0] = i;
+        let mut row_min = cur[0];
+
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            cur[j] = (prev[j] + 1).min(cur[j - 1] + 1).min(prev[j - 1] + cost);
+            row_min = row_min.min(cur[j]);
+        }
+
+        if row_min > max {
+            return None;
+        }
+
+        mem::swap(&mut prev, &mut cur);
+    }
+
+    Some(prev[b.len()]).filter(|&d| d <= max)
+}
+
+#[derive(Debug, Clone)]
+enum MatcherKind {
+    Regex(Regex),
+    Fuzzy(Vec<(String, usize)>),
+}
+
+/// A search key compiled according to a `MatchCondition`.
+#[derive(Debug, Clone)]
+pub struct Matcher {
+    kind: MatcherKind,
+    /// Literal keys match a tag and its subtree, e.g. "proj" matches "proj/web".
+    tag: Option<Regex>,
+    ignore_case: bool,
+    match_word: bool,
+}
+
+impl Matcher {
+    fn glob_to_regex(key: &str) -> String {
+        let mut re = String::new();
+        let mut chars = key.chars();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '*' => re.push_str(".*"),
+                '?' => re.push('.'),
+                '\\' => {
+                    if let Some(e) = chars.next() {
+                        re.push_str(&regex::escape(&e.to_string()));
+                    }
+                }
+                c => re.push_str(&regex::escape(&c.to_string())),
+            }
+        }
+
+        re
+    }
+
+    pub fn new(key: &str, condition: MatchCondition) -> Result<Self, MemoError> {
+        let key = &*normalize(key);
+
+        if condition.mode == MatchMode::Fuzzy {
+            let words: Vec<(String, usize)> = Matcher::words(key, condition.ignore_case)
+                .into_iter()
+                .map(|w| {
+                    let d = condition.distance_for(&w);
+                    (w, d)
+                })
+                .collect();
+
+            if words.is_empty() {
+                return Err(Report::new(MemoError::InvalidValue))
+                    .attach_printable(format!("No word to fuzzy match in `{key}`"));
+            }
+
+            return Ok(Self {
+                kind: MatcherKind::Fuzzy(words),
+                tag: None,
+                ignore_case: condition.ignore_case,
+                match_word: condition.match_word,
+            });
+        }
+
+        let pattern = match condition.mode {
+            MatchMode::Glob => Matcher::glob_to_regex(key),
+            MatchMode::Regex => format!("(?:{key})"),
+            _ => regex::escape(key),
+        };
+
+        let re = regex::RegexBuilder::new(&pattern)
+            .case_insensitive(condition.ignore_case)
+            .build()
+            .map_err(|e| {
+                Report::new(MemoError::InvalidValue)
+                    .attach_printable(format!("Invalid search key `{key}`: {e}"))
+            })?;
+
+        let tag = if condition.mode == MatchMode::Literal {
+            // With "-W" only the tag itself matches, not its subtree.
+            let key = regex::escape(key.trim_end_matches('/'));
+            let pattern = if condition.match_word {
+                format!("^{key}$")
+            } else {
+                format!("^{key}(?:/|$)")
+            };
+
+            regex::RegexBuilder::new(&pattern)
+                .case_insensitive(condition.ignore_case)
+                .build()
+                .ok()
+        } else {
+            None
+        };
+
+        Ok(Self {
+            kind: MatcherKind::Regex(re),
+            tag,
+            ignore_case: condition.ignore_case,
+            match_word: condition.match_word,
+        })
+    }
+
+    fn is_word(c: char) -> bool {
+        c.is_alphanumeric() || c == '_'
+    }
+
+    /// Whether there is a word boundary between `a` and `b`.
+    ///
+    /// Japanese and Chinese are written without spaces, so a CJK character is taken as a word
+    /// of its own, e.g. "Rust" is a word in "Rustの本". A missing side like the start of the
+    /// text is always a boundary.
+    fn is_boundary(a: Option<char>, b: Option<char>) -> bool {
+        match (a, b) {
+            (Some(a), Some(b)) => {
+                !(Matcher::is_word(a) && Matcher::is_word(b)) || is_cjk(a) || is_cjk(b)
+            }
+            _ => true,
+        }
+    }
+
+    /// Matches of `re` in `s`, with "-W" only the ones which are whole words.
+    fn find_iter<'s>(&self, re: &'s Regex, s: &'s str) -> impl Iterator<Item = Match<'s>> + 's {
+        let match_word = self.match_word;
+        let mut pos = 0;
+
+        std::iter::from_fn(move || {
+            while pos <= s.len() {
+                let m = re.find_at(s, pos)?;
+                let next = s[m.start()..]
+                    .chars()
+                    .next()
+                    .map_or(s.len() + 1, |c| m.start() + c.len_utf8());
+
+                if !match_word
+                    || (Matcher::is_boundary(
+                        s[..m.start()].chars().next_back(),
+                        s[m.start()..].chars().next(),
+                    ) && Matcher::is_boundary(
+                        s[..m.end()].chars().next_back(),
+                        s[m.end()..].chars().next(),
+                    ))
+                {
+                    pos = if m.end() > m.start() { m.end() } else { next };
+                    return Some(m);
+                }
+
+                pos = next;
+            }
+
+            None
+        })
+    }
+
+    /// Split a text into words, also used as terms of the search index.
+    ///
+    /// Words are in NFC and case folded with `ignore_case`.
+    pub fn words(s: &str, ignore_case: bool) -> Vec<String> {
+        normalize(s)
+            .split(|c: char| !Matcher::is_word(c))
+            .filter(|w| !w.is_empty())
+            .map(|w| {
+                if ignore_case {
+                    normalize_lower(w)
+                } else {
+                    w.to_owned()
+                }
+            })
+            .collect()
+    }
+
+    /// Count of words in `s` within the allowed distance of every key word.
+    fn fuzzy_hits(&self, keys: &[(String, usize)], s: &str) -> Vec<usize> {
+        let mut hits = vec![0; keys.len()];
+
+        for w in Matcher::words(s, self.ignore_case) {
+            for (i, (k, d)) in keys.iter().enumerate() {
+                if edit_distance(k, &w, *d).is_some() {
+                    hits[i] += 1;
+                }
+            }
+        }
+
+        hits
+    }
+
+    pub fn is_match(&self, s: &str) -> bool {
+        match &self.kind {
+            MatcherKind::Regex(re) => self.find_iter(re, &normalize(s)).next().is_some(),
+            MatcherKind::Fuzzy(keys) => self.fuzzy_hits(keys, s).iter().all(|&h| h > 0),
+        }
+    }
+
+    /// Match a tag name given without "[]".
+    pub fn is_tag_match(&self, tag: &str) -> bool {
+        match &self.tag {
+            Some(re) => re.is_match(&normalize(tag)),
+            None => self.is_match(tag),
+        }
+    }
+
+    pub fn count(&self, s: &str) -> usize {
+        match &self.kind {
+            MatcherKind::Regex(re) => self.find_iter(re, &normalize(s)).count(),
+            MatcherKind::Fuzzy(keys) => self.fuzzy_hits(keys, s).into_iter().min().unwrap_or(0),
+        }
+    }
+}
+
+/// Types of notes, i.e. suffixes of the files.
+pub const KINDS: [&str; 3] = ["txt", "html", "md"];
+
+#[derive(Debug, Clone)]
 pub struct FileName {
     year: String,
     month: String,
@@ -42,13 +329,30 @@ pub struct FileName {
 }
 
 impl FileName {
-    pub fn create(html: bool) -> Self {
-        let now = Local::now();
-        let mut suffix = String::from("txt");
-        if html {
-            suffix = String::from("html");
+    /// Name of a new note of `kind`, one of `KINDS`, in `memo_dir`. The creation time is moved
+    /// forward while it is taken by another note.
+    pub fn create(memo_dir: &str, kind: &str) -> Self {
+        let mut now = Local::now().naive_local();
+        while FileName::from_datetime(now, kind).taken(memo_dir) {
+            now += Duration::seconds(1);
         }
 
+        FileName::from_datetime(now, kind)
+    }
+
+    /// Whether a note of any kind in `memo_dir` has the creation time of this name, which
+    /// would give both notes the same ID.
+    pub fn taken(&self, memo_dir: &str) -> bool {
+        KINDS.iter().any(|k| {
+            let name = FileName {
+                suffix: (*k).to_owned(),
+                ..self.clone()
+            };
+            Path::new(&format!("{memo_dir}/{}", name.file_name())).exists()
+        })
+    }
+
+    pub fn from_datetime(now: NaiveDateTime, kind: &str) -> Self {
         Self {
             year: now.year().to_string(),
             month: now.month().to_string(),
@@ -56,7 +360,7 @@ impl FileName {
             hour: now.hour().to_string(),
             minute: now.minute().to_string(),
             second: now.second().to_string(),
-            suffix,
+            suffix: kind.to_owned(),
         }
     }
 
@@ -67,6 +371,57 @@ impl FileName {
         )
     }
 
+    /// Creation time encoded in the file name.
+    pub fn datetime(&self) -> Option<NaiveDateTime> {
+        NaiveDate::from_ymd_opt(
+            self.year.parse().ok()?,
+            self.month.parse().ok()?,
+            self.day.parse().ok()?,
+        )?
+        .and_hms_opt(
+            self.hour.parse().ok()?,
+            self.minute.parse().ok()?,
+            self.second.parse().ok()?,
+        )
+    }
+
+    /// 64 bits FNV-1a of the creation time, which is stable across builds unlike
+    /// `DefaultHasher`.
+    fn fnv1a(&self) -> u64 {
+        let mut hash: u64 = 0xcbf29ce484222325;
+        let stamp = format!(
+            "{:0>4}{:0>2}{:0>2}{:0>2}{:0>2}{:0>2}",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        );
+
+        for b in stamp.bytes() {
+            hash ^= b as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+
+        hash
+    }
+
+    /// Stable identifier of the note, a hash of the creation time in hexadecimal.
+    ///
+    /// The suffix is not part of it, so the identifier does not change with the type of the note.
+    pub fn id(&self) -> String {
+        // FNV-1a alone changes only the last digits for close times, fmix64 of murmur3 spreads
+        // the change over all of them so that short IDs differ.
+        let mut hash = self.fnv1a();
+        hash ^= hash >> 33;
+        hash = hash.wrapping_mul(0xff51afd7ed558ccd);
+        hash ^= hash >> 33;
+        hash = hash.wrapping_mul(0xc4ceb9fe1a85ec53);
+        hash ^= hash >> 33;
+
+        format!("{hash:016x}")
+    }
+
+    pub fn suffix(&self) -> &str {
+        &self.suffix
+    }
+
     pub fn file_name(&self) -> String {
         format!(
             "{:0>4}_{:0>2}_{:0>2}_{:0>2}_{:0>2}_{:0>2}.{}",
@@ -78,10 +433,10 @@ impl FileName {
         let path = Path::new(name);
         let create_time = path
             .file_stem()
-            .ok_or(
+            .ok_or_else(|| {
                 Report::new(MemoError::InvalidValue)
-                    .attach_printable(format!("Invalid file name {name}")),
-            )?
+                    .attach_printable(format!("Invalid file name {name}"))
+            })?
             .to_str()
             .unwrap();
 
@@ -90,7 +445,7 @@ impl FileName {
             .map(|a| a.to_str().unwrap())
             .unwrap_or("txt");
 
-        if suffix != "txt" && suffix != "html" {
+        if !KINDS.contains(&suffix) {
             jerror!("Invalid suffix for {name}");
             return Err(Report::new(MemoError::InvalidValue)).attach_printable("Invalid suffix");
         }
@@ -100,10 +455,10 @@ impl FileName {
 
         let mut field = |f: &str| -> Result<String, MemoError> {
             Ok(s.pop()
-                .ok_or(
+                .ok_or_else(|| {
                     Report::new(MemoError::InvalidValue)
-                        .attach_printable(format!("No {f} entry in file name")),
-                )?
+                        .attach_printable(format!("No {f} entry in file name"))
+                })?
                 .to_string())
         };
 
@@ -138,10 +493,65 @@ impl PartialEq for FileName {
     }
 }
 
+/// Length of the identifier shown to users, longer prefixes are also accepted.
+pub const SHORT_ID_LEN: usize = 7;
+
+/// Shortest identifier prefix accepted.
+pub const MIN_ID_LEN: usize = 4;
+
+/// Text in NFC, so that the same characters composed differently compare equal.
+pub fn normalize(s: &str) -> Cow<'_, str> {
+    if is_nfc(s) {
+        Cow::Borrowed(s)
+    } else {
+        Cow::Owned(s.nfc().collect())
+    }
+}
+
+/// Text in NFC and lower case, for comparisons ignoring case.
+pub fn normalize_lower(s: &str) -> String {
+    normalize(s).to_lowercase()
+}
+
+/// Characters of scripts written without spaces between words.
+pub fn is_cjk(c: char) -> bool {
+    matches!(c as u32,
+        0x3040..=0x30ff // Hiragana, Katakana
+        | 0x3400..=0x4dbf // CJK Extension A
+        | 0x4e00..=0x9fff // CJK Unified Ideographs
+        | 0xf900..=0xfaff // CJK Compatibility Ideographs
+        | 0xff66..=0xff9f // Halfwidth Katakana
+        | 0x20000..=0x2fa1f // CJK Extension B and later
+    )
+}
+
+/// Regex of a tag, a word wrapped by "[]" in the title line.
+///
+/// Tags are letters and digits of any script like "[日本語]" or "[café]". Hierarchical tags
+/// are separated by "/" like "[proj/backend]", and a tag may have ":" like "[lang:rust]".
+pub fn tag_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r"(\[[\p{L}\p{M}\p{N}_:-]+(?:/[\p{L}\p{M}\p{N}_:-]+)*\])").unwrap()
+    })
+}
+
+/// Whether `name` can be used as a tag, it is given without "[]".
+pub fn is_tag_name(name: &str) -> bool {
+    let tag = format!("[{name}]");
+    tag_regex().find(&tag).is_some_and(|m| m.as_str() == tag)
+}
+
+/// Front matter fields with a meaning of their own, the others are custom fields.
+const FRONT_FIELDS: [&str; 5] = ["title", "tags", "created", "updated", "aliases"];
+
 pub struct MemoEntry {
+    id: String,
     title: String,
-    body: String,
+    /// Read when it is needed for a note loaded from the index.
+    body: OnceCell<String>,
     tags: Vec<String>,
+    front: FrontMatter,
     name: FileName,
     full_path: String,
 }
@@ -179,68 +589,153 @@ impl MemoEntry {
             .to_str()
             .unwrap()
             .to_owned();
-        let mut reader = BufReader::new(f);
-        let mut title = String::new();
-        let mut body = String::new();
-        let mut tags = vec![];
-
-        loop {
-            let mut line = String::new();
-
-            let size = reader.read_line(&mut line).map_err(|e| {
-                Report::new(MemoError::IOError)
-                    .attach_printable(format!("Failed to open {file} : {e}"))
+        let mut content = String::new();
+        BufReader::new(f)
+            .read_to_string(&mut content)
+            .map_err(|e| {
+                let error = if e.kind() == ErrorKind::InvalidData {
+                    MemoError::BrokenNote
+                } else {
+                    MemoError::IOError
+                };
+                Report::new(error).attach_printable(format!("Failed to read {file} : {e}"))
             })?;
 
-            if size == 0 {
-                break;
-            }
-
-            if title.is_empty() {
-                title.push_str(&line);
-                title = title.trim_end_matches('\n').to_owned();
-
-                let re = Regex::new(r"(\[[a-z|A-Z|0-9|_|-]+\])").unwrap();
-                tags = re
-                    .find_iter(&title)
-                    .map(|m| m.as_str().to_owned())
-                    .collect();
-
-                /*
-                let re = Regex::new(r"(?<tag>\[[a-z|A-Z|0-9|_|-]+\])").unwrap();
-                let mut it = re.captures_iter(&title);
-
-                jdebug!(title = title);
-                while let Some(t) = it.next() {
-                    let s = &t["tag"];
-                    jdebug!(s = s);
-                    tags.push(s.to_owned());
+        let (front, title, body) = MemoEntry::split(&content, name.suffix() == "md");
+        let mut tags: Vec<String> = vec![];
+        let front_tags = front.get("tags").map(|t| t.items()).unwrap_or_default();
+        for tag in tag_regex()
+            .find_iter(&title)
+            .map(|m| m.as_str().to_owned())
+            .chain(front_tags.into_iter().filter_map(|t| {
+                let t = t.trim_start_matches('[').trim_end_matches(']');
+                if is_tag_name(t) {
+                    Some(format!("[{t}]"))
+                } else {
+                    jwarn!("{file}: invalid tag `{t}` is ignored.");
+                    None
                 }
-                */
-            } else {
-                body.push_str(&line);
+            }))
+        {
+            if !tags.contains(&tag) {
+                tags.push(tag);
             }
         }
 
         if title.trim().is_empty() {
             return Err(
-                Report::new(MemoError::Unexpected).attach_printable(format!("{file} is empty"))
+                Report::new(MemoError::BrokenNote).attach_printable(format!("{file} has no title"))
             );
         }
 
         Ok(Self {
+            id: name.id(),
             title,
-            body,
+            body: OnceCell::from(body),
             tags,
+            front,
+            name,
+            full_path,
+        })
+    }
+
+    /// Split the content of a note into the front matter, the title and the body.
+    ///
+    /// Notes may start with a front matter, otherwise the first line is the title.
+    fn split(content: &str, md: bool) -> (FrontMatter, String, String) {
+        let (front, rest) = match FrontMatter::parse(content) {
+            Some((front, rest)) => (front, rest),
+            None => (FrontMatter::default(), content),
+        };
+
+        let mut title = front
+            .get("title")
+            .and_then(|t| t.as_str())
+            .unwrap_or_default()
+            .to_owned();
+        let mut body = String::new();
+
+        for line in rest.split_inclusive('\n') {
+            if title.is_empty() {
+                title = line.trim_end_matches('\n').to_owned();
+                if md {
+                    title = Markdown::heading(&title).to_owned();
+                }
+            } else {
+                body.push_str(line);
+            }
+        }
+
+        (front, title, body)
+    }
+
+    /// A note known by the index, the file is not read until the body is needed.
+    pub fn indexed(
+        full_path: String,
+        title: &str,
+        tags: &[String],
+        front: &str,
+    ) -> Result<MemoEntry, MemoError> {
+        let file_name = Path::new(&full_path)
+            .file_name()
+            .and_then(|a| a.to_str())
+            .unwrap_or_default();
+        let name = FileName::from_file_name(file_name)?;
+
+        Ok(Self {
+            id: name.id(),
+            title: title.to_owned(),
+            body: OnceCell::new(),
+            tags: tags.to_vec(),
+            front: FrontMatter::parse(front)
+                .map(|(f, _)| f)
+                .unwrap_or_default(),
             name,
             full_path,
         })
     }
 
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn short_id(&self) -> &str {
+        &self.id[..SHORT_ID_LEN]
+    }
+
+    /// Type of the note, i.e. the suffix of the file.
+    pub fn kind(&self) -> &str {
+        self.name.suffix()
+    }
+
+    pub fn match_id(&self, prefix: &str) -> bool {
+        prefix.len() >= MIN_ID_LEN && self.id.starts_with(&prefix.to_lowercase())
+    }
+
     pub fn title(&self) -> &str {
         &self.title
     }
 
+    pub fn body(&self) -> &str {
+        self.body
+            .get_or_init(|| match fs::read_to_string(&self.full_path) {
+                Ok(content) => MemoEntry::split(&content, self.kind() == "md").2,
+                Err(e) => {
+                    jwarn!("Failed to read {}: {e}", self.full_path);
+                    String::new()
+                }
+            })
+    }
+
+    /// Front matter block as it is written in the note.
+    pub fn front(&self) -> &str {
+        self.front.source()
+    }
+
+    pub fn tag_list(&self) -> &[String] {
+        &self.tags
+    }
+
     pub fn tags(&self) -> String {
         let mut tag = String::new();
 
@@ -252,48 +747,105 @@ impl MemoEntry {
     }
 
     pub fn create_time(&self) -> String {
-        self.name.create_time()
+        match self.created() {
+            Some(t) => t.format("%Y/%m/%d %H:%M:%S").to_string(),
+            None => self.name.create_time(),
+        }
     }
 
-    pub fn match_tag(&self, tag: &str, condition: MatchCondition) -> bool {
-        let key = if condition.match_word {
-            format!(r"\b{tag}\b")
-        } else {
-            tag.to_owned()
-        };
+    /// Creation time from the front matter, or the one encoded in the file name.
+    pub fn created(&self) -> Option<NaiveDateTime> {
+        self.front
+            .get("created")
+            .and_then(|t| t.as_str())
+            .and_then(FrontMatter::datetime)
+            .or_else(|| self.name.datetime())
+    }
 
-        let re = regex::RegexBuilder::new(&key)
-            .case_insensitive(condition.ignore_case)
-            .build()
-            .unwrap();
+    pub fn updated(&self) -> Option<NaiveDateTime> {
+        self.front
+            .get("updated")
+            .and_then(|t| t.as_str())
+            .and_then(FrontMatter::datetime)
+    }
+
+    /// Other names of the note, searched like the title.
+    pub fn aliases(&self) -> Vec<&str> {
+        self.front
+            .get("aliases")
+            .map(|a| a.items())
+            .unwrap_or_default()
+    }
+
+    /// Custom fields of the front matter.
+    pub fn meta(&self) -> impl Iterator<Item = (&String, &Value)> {
+        self.front
+            .fields()
+            .iter()
+            .filter(|(k, _)| !FRONT_FIELDS.contains(&k.as_str()))
+    }
 
+    pub fn match_created(&self, range: &DateRange) -> bool {
+        self.created().map(|t| range.contains(t)).unwrap_or(false)
+    }
+
+    /// Whether a tag or its subtree matches, like "--tag proj" matches "[proj/web]".
+    pub fn match_tag(&self, matcher: &Matcher) -> bool {
         self.tags
             .iter()
-            .any(|a| re.is_match(a.trim_matches('[').trim_matches(']')))
+            .any(|a| matcher.is_tag_match(a.trim_matches('[').trim_matches(']')))
     }
 
-    pub fn match_content(&self, key: &str, condition: MatchCondition) -> bool {
-        let key = if condition.match_word {
-            format!(r"\b{key}\b")
-        } else {
-            key.to_owned()
-        };
+    pub fn match_title(&self, matcher: &Matcher) -> bool {
+        matcher.is_match(&self.title) || self.aliases().iter().any(|a| matcher.is_match(a))
+    }
 
-        let re = regex::RegexBuilder::new(&key)
-            .case_insensitive(condition.ignore_case)
-            .build()
-            .unwrap();
+    pub fn match_body(&self, matcher: &Matcher) -> bool {
+        matcher.is_match(self.body())
+    }
 
-        re.is_match(&self.title) || re.is_match(&self.body)
+    pub fn match_content(&self, matcher: &Matcher) -> bool {
+        self.match_title(matcher) || self.match_body(matcher)
     }
 
-    pub fn match_any(&self, key: &str, condition: MatchCondition) -> bool {
-        self.match_tag(key, condition) || self.match_content(key, condition)
+    pub fn has_meta(&self, name: &str) -> bool {
+        self.front.get(name).is_some()
+    }
+
+    /// Whether the front matter field `name`, or any item of it, satisfies `f`.
+    pub fn match_meta<F>(&self, name: &str, f: F) -> bool
+    where
+        F: Fn(&str) -> bool,
+    {
+        self.front
+            .get(name)
+            .is_some_and(|v| v.items().into_iter().any(f))
+    }
+
+    pub fn match_ext(&self, ext: &str) -> bool {
+        self.name
+            .suffix()
+            .eq_ignore_ascii_case(ext.trim_start_matches('.'))
+    }
+
+    /// Keywords match a part of tags too, unlike `match_tag`.
+    pub fn match_any(&self, matcher: &Matcher) -> bool {
+        self.tags
+            .iter()
+            .any(|a| matcher.is_match(a.trim_matches('[').trim_matches(']')))
+            || self.match_content(matcher)
     }
 
     pub fn full_path(&self) -> &str {
         &self.full_path
     }
+
+    pub fn file_name(&self) -> &str {
+        Path::new(&self.full_path)
+            .file_name()
+            .and_then(|a| a.to_str())
+            .unwrap_or(&self.full_path)
+    }
 }
 
 impl PartialEq for MemoEntry {
@@ -305,11 +857,13 @@ impl PartialEq for MemoEntry {
 pub struct Memo {
     entries: Vec<MemoEntry>,
     root: String,
+    index: Index,
 }
 
 #[allow(unused)]
 impl Memo {
-    fn setup_root(root_path: Option<&str>) -> Result<(String, String), MemoError> {
+    /// Root path and the note directory under it, they are created if missing.
+    pub fn setup_root(root_path: Option<&str>) -> Result<(String, String), MemoError> {
         let mut root = format!("{}/.memo", env!("HOME"));
 
         if let Some(r) = root_path {
@@ -342,9 +896,16 @@ impl Memo {
         Ok((root, memo_dir))
     }
 
+    /// Load the notes under `root_path`, a note not modified since it was indexed is taken
+    /// from the index and its file is read only when the body is needed.
     pub fn load(root_path: Option<&str>) -> Result<Self, MemoError> {
         let mut entries = Vec::new();
+        let mut skipped = vec![];
         let (root, memo_dir) = Memo::setup_root(root_path)?;
+        let mut index = Index::load(&root);
+        let full_dir = fs::canonicalize(&memo_dir)
+            .map(|d| d.to_string_lossy().into_owned())
+            .unwrap_or_else(|_| memo_dir.clone());
 
         let m_path = Path::new(&memo_dir);
         let mut it = fs::read_dir(m_path).map_err(|e| {
@@ -362,28 +923,74 @@ impl Memo {
                     continue;
                 }
 
+                if let Some(reason) = Doctor::unrecognized(p) {
+                    jdebug!("Skip {f}: {reason}.");
+                    if !Doctor::is_hidden(p) {
+                        skipped.push(f.to_owned());
+                    }
+                    continue;
+                }
+
+                let indexed = p
+                    .file_name()
+                    .and_then(|n| n.to_str())
+                    .and_then(|n| index.entry(&format!("{full_dir}/{n}"), Index::mtime(f)));
+                if let Some(m) = indexed {
+                    entries.push(m);
+                    continue;
+                }
+
                 match MemoEntry::load(f) {
                     Ok(m) => {
                         entries.push(m);
                     }
-                    Err(e) => {
-                        jwarn!("Failed to load {f}, remove it:\n{:?}.", e);
-                        if let Err(e) = fs::remove_file(f) {
-                            jwarn!("Failed to remove {f}:\n{:?}", e);
+                    Err(e) if matches!(e.current_context(), MemoError::BrokenNote) => {
+                        // It may be a note being written right now.
+                        if !Doctor::settled(p) {
+                            jdebug!("Skip {f} modified recently: {}", Doctor::reason(&e));
+                            skipped.push(f.to_owned());
+                            continue;
+                        }
+
+                        match Doctor::quarantine(&root, f) {
+                            Ok(to) => {
+                                jwarn!("{f} is broken, moved to {to}: {}", Doctor::reason(&e))
+                            }
+                            Err(e) => jwarn!("Failed to quarantine {f}, skip it:\n{:?}", e),
                         }
                     }
+                    Err(e) => {
+                        jwarn!("Failed to load {f}, skip it:\n{:?}", e);
+                    }
                 }
             }
         }
 
-        Ok(Self { entries, root })
+        index.update(&entries);
+        if let Err(e) = index.save() {
+            jwarn!("Failed to save index:\n{:?}", e);
+        }
+        Doctor::report_skipped(&root, &memo_dir, skipped);
+
+        Ok(Self {
+            entries,
+            root,
+            index,
+        })
+    }
+
+    /// Rebuild the search index from scratch, returns the number of indexed notes.
+    pub fn reindex(&mut self) -> Result<usize, MemoError> {
+        self.index.rebuild(&self.entries);
+        self.index.save()?;
+        Ok(self.index.len())
     }
 
     pub fn is_empty(&self) -> bool {
         self.entries.is_empty()
     }
 
-    pub fn new_search(&self) -> MemoSearch {
+    pub fn new_search(&self) -> MemoSearch<'_> {
         MemoSearch {
             entries: vec![],
             root: &self.root,
@@ -393,31 +1000,46 @@ impl Memo {
     pub fn find(
         &self,
         key_pair: Option<(&str, bool, MatchCondition)>,
-    ) -> Result<MemoSearch, MemoError> {
-        let mut result = vec![];
+    ) -> Result<MemoSearch<'_>, MemoError> {
         if let Some((key, is_tag, condition)) = key_pair {
-            for entry in &self.entries {
+            self.find_key(key, condition, |entry, matcher| {
                 if is_tag {
-                    if entry.match_tag(key, condition) {
-                        result.push(entry);
-                    }
-                } else if entry.match_any(key, condition) {
-                    result.push(entry);
+                    entry.match_tag(matcher)
+                } else {
+                    entry.match_any(matcher)
                 }
-            }
+            })
         } else {
-            for entry in &self.entries {
-                result.push(entry);
-            }
+            self.find_else(|_| true)
         }
+    }
 
-        Ok(MemoSearch {
-            entries: result,
-            root: &self.root,
+    /// Search notes by `key`, the key is compiled only once and the search index is used to
+    /// skip notes which can not match before `cb` is called.
+    pub fn find_key<F>(
+        &self,
+        key: &str,
+        condition: MatchCondition,
+        cb: F,
+    ) -> Result<MemoSearch<'_>, MemoError>
+    where
+        F: Fn(&MemoEntry, &Matcher) -> bool,
+    {
+        let matcher = Matcher::new(key, condition)?;
+        let candidates = self.index.candidates(key, condition);
+
+        self.find_else(|entry| {
+            if let Some(c) = &candidates {
+                if !c.contains(entry.file_name()) {
+                    return false;
+                }
+            }
+
+            cb(entry, &matcher)
         })
     }
 
-    pub fn find_else<F>(&self, cb: F) -> Result<MemoSearch, MemoError>
+    pub fn find_else<F>(&self, cb: F) -> Result<MemoSearch<'_>, MemoError>
     where
         F: Fn(&MemoEntry) -> bool,
     {
@@ -439,19 +1061,219 @@ impl Memo {
         &self.root
     }
 
-    pub fn create(root_path: Option<&str>, is_html: bool) -> Result<(), MemoError> {
+    /// Create a note with the editor, pre-filled from `template` if given.
+    ///
+    /// The type of the note follows the template. A note from a template is not saved if it
+    /// is left unchanged.
+    pub fn create(
+        root_path: Option<&str>,
+        kind: &str,
+        template: Option<&str>,
+    ) -> Result<Option<MemoEntry>, MemoError> {
+        let (root, memo_dir) = Memo::setup_root(root_path)?;
+
+        let Some(name) = template else {
+            let output = format!(
+                "{memo_dir}/{}",
+                FileName::create(&memo_dir, kind).file_name()
+            );
+            return Memo::edit(&root, &output);
+        };
+
+        let template = Template::load(&root, name, kind)?;
+        let content = template.expand()?;
+        let output = format!(
+            "{memo_dir}/{}",
+            FileName::create(&memo_dir, template.kind()).file_name()
+        );
+
+        fs::write(&output, &content).map_err(|e| {
+            Report::new(MemoError::IOError)
+                .attach_printable(format!("Failed to write {output}: {e}"))
+        })?;
+
+        let entry = Memo::edit_note(&root, &output, false)?;
+        if entry.is_some() && fs::read_to_string(&output).ok().as_deref() == Some(&content) {
+            jinfo!("{output} is not changed from the template, remove it.");
+            fs::remove_file(&output).map_err(|e| {
+                Report::new(MemoError::IOError)
+                    .attach_printable(format!("Failed to remove {output}: {e}"))
+            })?;
+            return Ok(None);
+        }
+
+        Ok(entry)
+    }
+
+    /// Create a note without the editor from a title, tags and a body.
+    ///
+    /// Tags are put in front of the title in the "[tag]" format, and the title of a markdown
+    /// note is made a heading. If the body starts with a front matter, the title and the tags
+    /// are written in it instead and the body is kept as it is. If a note was created in
+    /// the same second, the creation time of the new note is moved forward so that both
+    /// notes keep their own file.
+    pub fn write(
+        root_path: Option<&str>,
+        kind: &str,
+        title: &str,
+        tags: &[String],
+        body: &str,
+    ) -> Result<MemoEntry, MemoError> {
+        if title.contains('\n') {
+            return Err(Report::new(MemoError::InvalidValue))
+                .attach_printable("Title should be a single line.");
+        }
+
+        let mut names = vec![];
+        for tag in tags {
+            let tag = tag.trim().trim_start_matches('[').trim_end_matches(']');
+            if !is_tag_name(tag) {
+                return Err(Report::new(MemoError::InvalidValue)).attach_printable(format!(
+                    "Invalid tag `{tag}`, use letters, digits, '_', '-', ':' and '/'."
+                ));
+            }
+            names.push(tag.to_owned());
+        }
+
+        let mut line: Vec<String> = names.iter().map(|t| format!("[{t}]")).collect();
+
+        if !title.trim().is_empty() {
+            line.push(title.trim().to_owned());
+        }
+
+        let mut content = if FrontMatter::parse(body).is_some() {
+            let title = Some(title.trim()).filter(|t| !t.is_empty());
+            FrontMatter::insert(body, title, &names)
+        } else {
+            let mut content = line.join(" ");
+            if kind == "md" && !content.is_empty() && !content.starts_with('#') {
+                content.insert_str(0, "# ");
+            }
+            content.push('\n');
+            content.push_str(body);
+            content
+        };
+        if !content.ends_with('\n') {
+            content.push('\n');
+        }
+
+        if content.trim().is_empty() {
+            return Err(Report::new(MemoError::InvalidValue))
+                .attach_printable("Empty note is not saved.");
+        }
+
+        if let Some((front, rest)) = FrontMatter::parse(&content) {
+            let titled = front.get("title").and_then(|t| t.as_str()).is_some()
+                || rest.lines().any(|l| !l.trim().is_empty());
+            if !titled {
+                return Err(Report::new(MemoError::InvalidValue))
+                    .attach_printable("Note has no title, set it in the front matter.");
+            }
+        }
+
         let (_root, memo_dir) = Memo::setup_root(root_path)?;
-        let file_name = FileName::create(is_html);
+        let mut now = Local::now().naive_local();
+
+        loop {
+            let output = format!(
+                "{memo_dir}/{}",
+                FileName::from_datetime(now, kind).file_name()
+            );
+            if FileName::from_datetime(now, kind).taken(&memo_dir) {
+                now += Duration::seconds(1);
+                continue;
+            }
+
+            match fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&output)
+            {
+                Ok(mut f) => {
+                    f.write_all(content.as_bytes()).map_err(|e| {
+                        Report::new(MemoError::IOError)
+                            .attach_printable(format!("Failed to write {output}: {e}"))
+                    })?;
+                    jdebug!("{output} is created.");
+                    return MemoEntry::load(&output);
+                }
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                    now += Duration::seconds(1);
+                }
+                Err(e) => {
+                    return Err(Report::new(MemoError::IOError))
+                        .attach_printable(format!("Failed to create {output}: {e}"))
+                }
+            }
+        }
+    }
+
+    /// Open an existing or new note `file` of the store `root` with the editor and check it
+    /// after the editor exits.
+    ///
+    /// A note left empty is removed, the same way an empty new note is not saved. The content
+    /// a note had before is kept in its history, or in the trash if the note is removed.
+    pub fn edit(root: &str, file: &str) -> Result<Option<MemoEntry>, MemoError> {
+        Memo::edit_note(root, file, true)
+    }
+
+    /// Like `edit`, the content before the editor is kept only if `saved`, i.e. it is not
+    /// something like a template filled in for a new note.
+    fn edit_note(root: &str, file: &str, saved: bool) -> Result<Option<MemoEntry>, MemoError> {
+        let old = if saved {
+            fs::read_to_string(file).unwrap_or_default()
+        } else {
+            String::new()
+        };
+        let written = History::modified(file);
+        Memo::open_editor(file)?;
+
+        let content = if Path::new(file).exists() {
+            fs::read_to_string(file).map_err(|e| {
+                Report::new(MemoError::IOError)
+                    .attach_printable(format!("Failed to read {file}: {e}"))
+            })?
+        } else {
+            String::new()
+        };
+
+        if content != old && !old.trim().is_empty() {
+            let time = written.unwrap_or_else(|| Local::now().naive_local());
+            if let Err(e) = History::save(root, file, &old, time) {
+                jwarn!("Failed to save the history of {file}:\n{:?}", e);
+            }
+        }
+
+        if content.trim().is_empty() {
+            if !old.trim().is_empty() {
+                let to = Trash::put_content(root, file, &old, "emptied")?;
+                jinfo!("{file} is emptied, the previous content is moved to {to}.");
+            } else {
+                jdebug!("{file} is not saved.");
+            }
+
+            if Path::new(file).exists() {
+                fs::remove_file(file).map_err(|e| {
+                    Report::new(MemoError::IOError)
+                        .attach_printable(format!("Failed to remove {file}: {e}"))
+                })?;
+            }
+            return Ok(None);
+        }
+
+        MemoEntry::load(file).map(Some)
+    }
 
-        let output = format!("{memo_dir}/{}", file_name.file_name());
+    /// Open `file` with "$EDITOR" and wait until the editor exits.
+    pub fn open_editor(file: &str) -> Result<(), MemoError> {
         let editor = env::var("EDITOR").unwrap_or("vim".to_owned());
-        let mut handle = Command::new(editor).arg(&output).spawn().map_err(|e| {
+        let mut handle = Command::new(&editor).arg(file).spawn().map_err(|e| {
             Report::new(MemoError::Unexpected)
-                .attach_printable(format!("Failed to execute vim: {e}"))
+                .attach_printable(format!("Failed to execute {editor}: {e}"))
         })?;
 
         handle.wait().map_err(|e| {
-            Report::new(MemoError::Unexpected).attach_printable(format!("vim failed: {e}"))
+            Report::new(MemoError::Unexpected).attach_printable(format!("{editor} failed: {e}"))
         })?;
 
         Ok(())
@@ -469,12 +1291,13 @@ impl<'a> MemoSearch<'a> {
     pub fn find(&self, key_pair: Option<(&str, bool, MatchCondition)>) -> Result<Self, MemoError> {
         let mut result = vec![];
         if let Some((key, is_tag, condition)) = key_pair {
+            let matcher = Matcher::new(key, condition)?;
             for &entry in &self.entries {
                 if is_tag {
-                    if entry.match_tag(key, condition) {
+                    if entry.match_tag(&matcher) {
                         result.push(entry);
                     }
-                } else if entry.match_any(key, condition) {
+                } else if entry.match_any(&matcher) {
                     result.push(entry);
                 }
             }
@@ -509,7 +1332,7 @@ impl<'a> MemoSearch<'a> {
         })
     }
 
-    pub fn entries(&self) -> Vec<&MemoEntry> {
+    pub fn entries(&self) -> Vec<&'a MemoEntry> {
         self.entries.clone()
     }
 
@@ -552,7 +1375,7 @@ impl<'a> Add for MemoSearch<'a> {
         }
 
         for &entry in &rhs.entries {
-            if !self.entries.iter().any(|&a| a == entry) {
+            if !self.entries.contains(&entry) {
                 self.entries.push(entry);
             }
         }
@@ -568,8 +1391,7 @@ impl<'a> Sub for MemoSearch<'a> {
             return Err(Report::new(MemoError::InvalidValue));
         }
 
-        self.entries
-            .retain(|&entry| !rhs.entries.iter().any(|&a| a == entry));
+        self.entries.retain(|&entry| !rhs.entries.contains(&entry));
         Ok(self)
     }
 }
@@ -581,8 +1403,7 @@ impl<'a> Mul for MemoSearch<'a> {
             return Err(Report::new(MemoError::InvalidValue));
         }
 
-        self.entries
-            .retain(|&entry| rhs.entries.iter().any(|&a| a == entry));
+        self.entries.retain(|&entry| rhs.entries.contains(&entry));
         Ok(self)
     }
 }
@@ -0,0 +1,891 @@
+#[allow(unused)]
+use {
+    super::{
+        date::DateRange,
+        error::MemoError,
+        front::FrontMatter,
+        memo::{
+            normalize, normalize_lower, MatchCondition, MatchMode, Matcher, Memo, MemoEntry,
+            MemoSearch,
+        },
+    },
+    error_stack::{Report, Result, ResultExt},
+    jlogger_tracing::{
+        jdebug, jerror, jinfo, jtrace, jwarn, JloggerBuilder, LevelFilter, LogTimeFormat,
+    },
+    std::{
+        cmp::Ordering,
+        fmt::{self, Debug, Display},
+        iter::Peekable,
+        str::{Chars, FromStr},
+    },
+};
+
+/// Tokens of the search query language.
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    /// A bare keyword, escapes already resolved.
+    Word(String),
+    /// A double quoted phrase, escapes already resolved.
+    Phrase(String),
+    /// A field prefix like `title:`, the value is the next token.
+    Field(Field),
+    /// A prefix of a front matter field like `status:` as it is written, the value is the
+    /// next token.
+    Meta(String),
+    /// `~` before a keyword to match it fuzzily.
+    Tilde,
+    Plus,
+    Minus,
+    Star,
+    LParen,
+    RParen,
+}
+
+impl Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Word(w) => write!(f, "{w}"),
+            Token::Phrase(p) => write!(f, "\"{p}\""),
+            Token::Field(field) => write!(f, "{field}:"),
+            Token::Meta(name) => write!(f, "{name}:"),
+            Token::Tilde => write!(f, "~"),
+            Token::Plus => write!(f, "+"),
+            Token::Minus => write!(f, "-"),
+            Token::Star => write!(f, "*"),
+            Token::LParen => write!(f, "("),
+            Token::RParen => write!(f, ")"),
+        }
+    }
+}
+
+fn is_operator(c: char) -> bool {
+    matches!(c, '+' | '-' | '*' | '(' | ')' | '"')
+}
+
+/// Push an escaped character. Wildcards stay escaped for glob patterns so that they
+/// are matched literally, and regular expressions keep all escapes but quotation mark.
+fn push_escaped(buf: &mut String, c: char, mode: MatchMode) {
+    let keep = match mode {
+        MatchMode::Literal | MatchMode::Fuzzy => false,
+        MatchMode::Glob => matches!(c, '*' | '?' | '\\'),
+        MatchMode::Regex => c != '"',
+    };
+
+    if keep {
+        buf.push('\\');
+    }
+
+    buf.push(c);
+}
+
+/// Operators at the end of a keyword which are not followed by an operand, like in `c++` or
+/// `C#-`. They are part of the keyword.
+fn trailing_operators(chars: &Peekable<Chars>) -> Option<String> {
+    let mut ahead = chars.clone();
+    let mut ops = String::new();
+    while let Some(&c) = ahead.peek() {
+        if !matches!(c, '+' | '-' | '*') {
+            break;
+        }
+        ops.push(c);
+        ahead.next();
+    }
+
+    match ahead.peek() {
+        Some(&c) if !c.is_whitespace() && c != ')' => None,
+        _ => Some(ops),
+    }
+}
+
+/// In glob mode `*` next to a keyword is a wildcard rather than the "and" operator.
+fn star_starts_word(chars: &Peekable<Chars>) -> bool {
+    let mut ahead = chars.clone();
+    ahead.next();
+    ahead
+        .peek()
+        .map(|&c| !c.is_whitespace() && (c == '*' || !is_operator(c)))
+        .unwrap_or(false)
+}
+
+fn read_phrase(
+    chars: &mut Peekable<Chars>,
+    query: &str,
+    mode: MatchMode,
+) -> Result<String, MemoError> {
+    let mut phrase = String::new();
+
+    // Skip the opening quotation mark.
+    chars.next();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                if let Some(e) = chars.next() {
+                    push_escaped(&mut phrase, e, mode);
+                }
+            }
+            '"' => return Ok(phrase),
+            c => phrase.push(c),
+        }
+    }
+
+    Err(Report::new(MemoError::InvalidValue))
+        .attach_printable(format!("Unterminated quote in query `{query}`"))
+}
+
+/// Read the value of a field, which may include operator characters like in
+/// `title:x86-64`, up to a whitespace or a parenthesis. A leading `~` is returned as a
+/// separate token.
+fn read_field_value(
+    chars: &mut Peekable<Chars>,
+    query: &str,
+    mode: MatchMode,
+) -> Result<Vec<Token>, MemoError> {
+    if let Some('"') = chars.peek() {
+        return Ok(vec![Token::Phrase(read_phrase(chars, query, mode)?)]);
+    }
+
+    if let Some('~') = chars.peek() {
+        chars.next();
+        let mut tokens = vec![Token::Tilde];
+        tokens.extend(read_field_value(chars, query, mode)?);
+        return Ok(tokens);
+    }
+
+    let mut value = String::new();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() || c == '(' || c == ')' {
+            break;
+        }
+
+        chars.next();
+        if c == '\\' {
+            if let Some(e) = chars.next() {
+                push_escaped(&mut value, e, mode);
+            }
+        } else {
+            value.push(c);
+        }
+    }
+
+    if value.is_empty() {
+        return Err(Report::new(MemoError::InvalidValue))
+            .attach_printable(format!("Missing field value in query `{query}`"));
+    }
+
+    Ok(vec![Token::Word(value)])
+}
+
+/// Names of front matter fields which can be queried, like `status` or `due-date`.
+fn is_meta_name(s: &str) -> bool {
+    let mut chars = s.chars();
+    chars.next().is_some_and(|c| c.is_alphabetic())
+        && chars.all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+}
+
+/// Whether `c` ends a keyword. A regular expression is read up to a whitespace, so `kube.+s`
+/// is one pattern, and a `)` closing a group of the expression is part of it.
+fn ends_word(c: char, mode: MatchMode, depth: usize) -> bool {
+    match mode {
+        _ if c.is_whitespace() => true,
+        MatchMode::Regex => c == '"' || (c == ')' && depth == 0),
+        MatchMode::Glob => c != '*' && is_operator(c),
+        _ => is_operator(c),
+    }
+}
+
+fn tokenize(query: &str, mode: MatchMode) -> Result<Vec<Token>, MemoError> {
+    let glob = mode == MatchMode::Glob;
+    let mut tokens = vec![];
+    let mut chars: Peekable<Chars> = query.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '+' => {
+                chars.next();
+                tokens.push(Token::Plus);
+            }
+            '-' => {
+                chars.next();
+                tokens.push(Token::Minus);
+            }
+            '*' if !(glob && star_starts_word(&chars)) => {
+                chars.next();
+                tokens.push(Token::Star);
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::LParen);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::RParen);
+            }
+            '"' => {
+                tokens.push(Token::Phrase(read_phrase(&mut chars, query, mode)?));
+            }
+            '~' => {
+                chars.next();
+                tokens.push(Token::Tilde);
+            }
+            _ => {
+                let mut word = String::new();
+                let mut escaped = false;
+                let mut depth = 0;
+
+                while let Some(&c) = chars.peek() {
+                    if ends_word(c, mode, depth) {
+                        if let Some(ops) = trailing_operators(&chars).filter(|_| !word.is_empty()) {
+                            for _ in 0..ops.chars().count() {
+                                chars.next();
+                            }
+                            word.push_str(&ops);
+                        }
+                        break;
+                    }
+
+                    chars.next();
+                    if c == '\\' {
+                        escaped = true;
+                        if let Some(e) = chars.next() {
+                            push_escaped(&mut word, e, mode);
+                        }
+                    } else if c == '(' || c == ')' {
+                        depth = if c == '(' { depth + 1 } else { depth - 1 };
+                        word.push(c);
+                    } else if c == ':' && !escaped {
+                        if let Ok(field) = word.parse::<Field>() {
+                            tokens.push(Token::Field(field));
+                            word.clear();
+                            break;
+                        }
+
+                        // `TODO:` without a value is a keyword.
+                        let value = chars
+                            .peek()
+                            .is_some_and(|c| !c.is_whitespace() && *c != ')');
+                        if value && is_meta_name(&word) {
+                            tokens.push(Token::Meta(word.clone()));
+                            word.clear();
+                            break;
+                        }
+                        word.push(c);
+                    } else {
+                        word.push(c);
+                    }
+                }
+
+                if let Some(Token::Field(_) | Token::Meta(_)) = tokens.last() {
+                    if word.is_empty() {
+                        tokens.extend(read_field_value(&mut chars, query, mode)?);
+                        continue;
+                    }
+                }
+
+                tokens.push(Token::Word(word));
+            }
+        }
+    }
+
+    jdebug!("tokens: {:?}", tokens);
+    Ok(tokens)
+}
+
+/// Part of a note a search term is matched against.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Field {
+    /// Tags, title and body.
+    Any,
+    Title,
+    Body,
+    Tag,
+    /// Suffix of the note file, like `txt` or `html`.
+    Ext,
+    /// Creation time encoded in the file name.
+    Created,
+    /// Identifier of the note, a prefix is enough.
+    Id,
+}
+
+impl FromStr for Field {
+    type Err = MemoError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "title" => Ok(Field::Title),
+            "body" => Ok(Field::Body),
+            "tag" => Ok(Field::Tag),
+            "ext" => Ok(Field::Ext),
+            "created" => Ok(Field::Created),
+            "id" => Ok(Field::Id),
+            _ => Err(MemoError::InvalidValue),
+        }
+    }
+}
+
+impl Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Field::Any => "any",
+            Field::Title => "title",
+            Field::Body => "body",
+            Field::Tag => "tag",
+            Field::Ext => "ext",
+            Field::Created => "created",
+            Field::Id => "id",
+        };
+
+        write!(f, "{name}")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CmpOp {
+    Eq,
+    Ne,
+    Ge,
+    Le,
+    Gt,
+    Lt,
+}
+
+/// Comparison with the value of a front matter field, like `>=2` of `priority:>=2`.
+///
+/// Values are compared as numbers if both are numbers, as dates if the field is a date, and
+/// as strings otherwise.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetaCmp {
+    op: CmpOp,
+    value: String,
+}
+
+impl MetaCmp {
+    fn parse(s: &str) -> Self {
+        for (prefix, op) in [
+            (">=", CmpOp::Ge),
+            ("<=", CmpOp::Le),
+            ("!=", CmpOp::Ne),
+            (">", CmpOp::Gt),
+            ("<", CmpOp::Lt),
+            ("=", CmpOp::Eq),
+        ] {
+            if let Some(value) = s.strip_prefix(prefix) {
+                return Self {
+                    op,
+                    value: value.to_owned(),
+                };
+            }
+        }
+
+        Self {
+            op: CmpOp::Eq,
+            value: s.to_owned(),
+        }
+    }
+
+    fn is(&self, ord: Ordering) -> bool {
+        match self.op {
+            CmpOp::Eq => ord == Ordering::Equal,
+            CmpOp::Ne => ord != Ordering::Equal,
+            CmpOp::Ge => ord != Ordering::Less,
+            CmpOp::Le => ord != Ordering::Greater,
+            CmpOp::Gt => ord == Ordering::Greater,
+            CmpOp::Lt => ord == Ordering::Less,
+        }
+    }
+
+    pub fn matches(&self, value: &str, ignore_case: bool) -> bool {
+        if let (Ok(a), Ok(b)) = (value.parse::<f64>(), self.value.parse::<f64>()) {
+            return a.partial_cmp(&b).is_some_and(|o| self.is(o));
+        }
+
+        if let Some(t) = FrontMatter::datetime(value) {
+            // `DateRange` gives `2026-01` the meaning of the whole month.
+            let (expr, negate) = match self.op {
+                CmpOp::Eq => (self.value.clone(), false),
+                CmpOp::Ne => (self.value.clone(), true),
+                CmpOp::Ge => (format!(">={}", self.value), false),
+                CmpOp::Le => (format!("<={}", self.value), false),
+                CmpOp::Gt => (format!(">{}", self.value), false),
+                CmpOp::Lt => (format!("<{}", self.value), false),
+            };
+
+            if let Ok(range) = DateRange::parse(&expr) {
+                return range.contains(t) != negate;
+            }
+        }
+
+        let (a, b) = if ignore_case {
+            (normalize_lower(value), normalize_lower(&self.value))
+        } else {
+            (
+                normalize(value).into_owned(),
+                normalize(&self.value).into_owned(),
+            )
+        };
+
+        self.is(a.cmp(&b))
+    }
+}
+
+/// A single search term of a query.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Term {
+    Key {
+        field: Field,
+        key: String,
+        fuzzy: bool,
+    },
+    Created(DateRange),
+    /// A front matter field, `key` is the whole term like `status:open` as it is written,
+    /// which is searched as a keyword if no note has the field.
+    Meta {
+        name: String,
+        cmp: MetaCmp,
+        key: String,
+    },
+}
+
+impl Term {
+    fn new(field: Field, key: String, fuzzy: bool) -> Result<Self, MemoError> {
+        match field {
+            Field::Created => Ok(Term::Created(DateRange::parse(&key)?)),
+            field => Ok(Term::Key { field, key, fuzzy }),
+        }
+    }
+
+    /// The keyword searched instead of a front matter field which no note has.
+    pub fn keyword(&self) -> Term {
+        match self {
+            Term::Meta { key, .. } => Term::Key {
+                field: Field::Any,
+                key: key.clone(),
+                fuzzy: false,
+            },
+            t => t.clone(),
+        }
+    }
+
+    /// Condition to match the term, a fuzzy term overrides the mode given by options.
+    pub fn condition(&self, condition: MatchCondition) -> MatchCondition {
+        match self {
+            Term::Key { fuzzy: true, .. } => MatchCondition {
+                mode: MatchMode::Fuzzy,
+                ..condition
+            },
+            _ => condition,
+        }
+    }
+
+    /// Search `memo` for notes matching the term.
+    pub fn find<'a>(
+        &self,
+        memo: &'a Memo,
+        condition: MatchCondition,
+    ) -> Result<MemoSearch<'a>, MemoError> {
+        match self {
+            Term::Key {
+                field: Field::Ext,
+                key,
+                ..
+            } => memo.find_else(|e| e.match_ext(key)),
+            Term::Key {
+                field: Field::Id,
+                key,
+                ..
+            } => memo.find_else(|e| e.match_id(key)),
+            Term::Key { field, key, .. } => {
+                memo.find_key(key, self.condition(condition), |e, matcher| match field {
+                    Field::Title => e.match_title(matcher),
+                    Field::Body => e.match_body(matcher),
+                    Field::Tag => e.match_tag(matcher),
+                    _ => e.match_any(matcher),
+                })
+            }
+            Term::Created(range) => memo.find_else(|e| e.match_created(range)),
+            Term::Meta { name, cmp, key } => {
+                let found = memo
+                    .find_else(|e| e.match_meta(name, |v| cmp.matches(v, condition.ignore_case)))?;
+                if found.is_empty() && memo.find_else(|e| e.has_meta(name))?.is_empty() {
+                    jdebug!("No note has `{name}`, search `{key}` as a keyword.");
+                    return self.keyword().find(memo, condition);
+                }
+
+                Ok(found)
+            }
+        }
+    }
+}
+
+/// Parsed search query.
+///
+/// Precedence from low to high: `+` (or) and binary `-` (without), `*` or juxtaposition (and),
+/// unary `-` (not). Parentheses group sub queries.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Query {
+    Term(Term),
+    Or(Box<Query>, Box<Query>),
+    Sub(Box<Query>, Box<Query>),
+    And(Box<Query>, Box<Query>),
+    Not(Box<Query>),
+}
+
+struct Parser<'a> {
+    tokens: Vec<Token>,
+    pos: usize,
+    query: &'a str,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let t = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        t
+    }
+
+    fn error(&self, msg: &str) -> Report<MemoError> {
+        Report::new(MemoError::InvalidValue)
+            .attach_printable(format!("{msg} in query `{}`", self.query))
+    }
+
+    fn parse_or(&mut self) -> Result<Query, MemoError> {
+        let mut lhs = self.parse_and()?;
+
+        loop {
+            match self.peek() {
+                Some(Token::Plus) => {
+                    self.next();
+                    let rhs = self.parse_and()?;
+                    lhs = Query::Or(Box::new(lhs), Box::new(rhs));
+                }
+                Some(Token::Minus) => {
+                    self.next();
+                    let rhs = self.parse_and()?;
+                    lhs = Query::Sub(Box::new(lhs), Box::new(rhs));
+                }
+                _ => break,
+            }
+        }
+
+        Ok(lhs)
+    }
+
+    fn parse_and(&mut self) -> Result<Query, MemoError> {
+        let mut lhs = self.parse_unary()?;
+
+        loop {
+            match self.peek() {
+                Some(Token::Star) => {
+                    self.next();
+                    let rhs = self.parse_unary()?;
+                    lhs = Query::And(Box::new(lhs), Box::new(rhs));
+                }
+                Some(Token::Word(_))
+                | Some(Token::Phrase(_))
+                | Some(Token::Field(_))
+                | Some(Token::Meta(_))
+                | Some(Token::Tilde)
+                | Some(Token::LParen) => {
+                    let rhs = self.parse_unary()?;
+                    lhs = Query::And(Box::new(lhs), Box::new(rhs));
+                }
+                _ => break,
+            }
+        }
+
+        Ok(lhs)
+    }
+
+    fn parse_unary(&mut self) -> Result<Query, MemoError> {
+        if let Some(Token::Minus) = self.peek() {
+            self.next();
+            let q = self.parse_unary()?;
+            return Ok(Query::Not(Box::new(q)));
+        }
+
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> Result<Query, MemoError> {
+        match self.next() {
+            Some(Token::LParen) => {
+                let q = self.parse_or()?;
+                match self.next() {
+                    Some(Token::RParen) => Ok(q),
+                    _ => Err(self.error("Missing `)`")),
+                }
+            }
+            Some(Token::Word(key)) | Some(Token::Phrase(key)) => {
+                Ok(Query::Term(Term::new(Field::Any, key, false)?))
+            }
+            Some(Token::Tilde) => match self.parse_primary()? {
+                Query::Term(Term::Key { field, key, .. }) => Ok(Query::Term(Term::Key {
+                    field,
+                    key,
+                    fuzzy: true,
+                })),
+                _ => Err(self.error("`~` must be followed by a keyword")),
+            },
+            Some(Token::Field(field)) => {
+                let fuzzy = self.peek() == Some(&Token::Tilde);
+                if fuzzy {
+                    self.next();
+                }
+
+                match self.next() {
+                    Some(Token::Word(key)) | Some(Token::Phrase(key)) => Ok(Query::Term(
+                        Term::new(field, key, fuzzy)
+                            .attach_printable(format!("in query `{}`", self.query))?,
+                    )),
+                    _ => Err(self.error(&format!("Missing value for `{field}:`"))),
+                }
+            }
+            Some(Token::Meta(name)) => match self.next() {
+                Some(Token::Word(value)) | Some(Token::Phrase(value)) => {
+                    Ok(Query::Term(Term::Meta {
+                        key: format!("{name}:{value}"),
+                        cmp: MetaCmp::parse(&value),
+                        name: name.to_lowercase(),
+                    }))
+                }
+                _ => Err(self.error(&format!("Missing value for `{name}:`"))),
+            },
+            Some(t) => Err(self.error(&format!("Unexpected `{t}`"))),
+            None => Err(self.error("Unexpected end")),
+        }
+    }
+}
+
+impl Query {
+    pub fn parse(query: &str, condition: MatchCondition) -> Result<Self, MemoError> {
+        let tokens = tokenize(query, condition.mode)?;
+        let mut parser = Parser {
+            tokens,
+            pos: 0,
+            query,
+        };
+
+        if parser.peek().is_none() {
+            return Err(parser.error("Empty keyword"));
+        }
+
+        let q = parser.parse_or()?;
+        if let Some(t) = parser.peek() {
+            return Err(parser.error(&format!("Unexpected `{t}`")));
+        }
+
+        jdebug!("query: {:?}", q);
+        Ok(q)
+    }
+
+    /// Terms which make a note be included in the result, i.e. terms not negated by `-`.
+    pub fn terms(&self) -> Vec<&Term> {
+        match self {
+            Query::Term(t) => vec![t],
+            Query::Or(lhs, rhs) | Query::And(lhs, rhs) => {
+                let mut terms = lhs.terms();
+                terms.extend(rhs.terms());
+                terms
+            }
+            Query::Sub(lhs, _) => lhs.terms(),
+            Query::Not(_) => vec![],
+        }
+    }
+
+    pub fn eval<'a>(
+        &self,
+        memo: &'a Memo,
+        condition: MatchCondition,
+    ) -> Result<MemoSearch<'a>, MemoError> {
+        let search = match self {
+            Query::Term(t) => t.find(memo, condition)?,
+            Query::Or(lhs, rhs) => (lhs.eval(memo, condition)? + rhs.eval(memo, condition)?)?,
+            Query::Sub(lhs, rhs) => (lhs.eval(memo, condition)? - rhs.eval(memo, condition)?)?,
+            Query::And(lhs, rhs) => (lhs.eval(memo, condition)? * rhs.eval(memo, condition)?)?,
+            Query::Not(q) => (memo.find(None)? - q.eval(memo, condition)?)?,
+        };
+
+        jdebug!("{:?} => {:?}", self, search);
+        Ok(search)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn condition(mode: MatchMode) -> MatchCondition {
+        MatchCondition {
+            ignore_case: false,
+            match_word: false,
+            mode,
+            max_distance: None,
+        }
+    }
+
+    fn parse(query: &str) -> Result<Query, MemoError> {
+        Query::parse(query, condition(MatchMode::Literal))
+    }
+
+    fn key(key: &str) -> Query {
+        Query::Term(Term::Key {
+            field: Field::Any,
+            key: key.to_owned(),
+            fuzzy: false,
+        })
+    }
+
+    fn or(a: Query, b: Query) -> Query {
+        Query::Or(Box::new(a), Box::new(b))
+    }
+
+    fn sub(a: Query, b: Query) -> Query {
+        Query::Sub(Box::new(a), Box::new(b))
+    }
+
+    fn and(a: Query, b: Query) -> Query {
+        Query::And(Box::new(a), Box::new(b))
+    }
+
+    fn not(a: Query) -> Query {
+        Query::Not(Box::new(a))
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        assert_eq!(
+            parse("a + b * c").unwrap(),
+            or(key("a"), and(key("b"), key("c")))
+        );
+        assert_eq!(
+            parse("a * b + c").unwrap(),
+            or(and(key("a"), key("b")), key("c"))
+        );
+        assert_eq!(
+            parse("a b + c").unwrap(),
+            or(and(key("a"), key("b")), key("c"))
+        );
+    }
+
+    #[test]
+    fn minus_is_left_associative() {
+        assert_eq!(
+            parse("a - b - c").unwrap(),
+            sub(sub(key("a"), key("b")), key("c"))
+        );
+        assert_eq!(parse("a-b").unwrap(), sub(key("a"), key("b")));
+    }
+
+    #[test]
+    fn parentheses_group() {
+        assert_eq!(
+            parse("(a + b) * c").unwrap(),
+            and(or(key("a"), key("b")), key("c"))
+        );
+        assert_eq!(
+            parse("a - (b - c)").unwrap(),
+            sub(key("a"), sub(key("b"), key("c")))
+        );
+    }
+
+    #[test]
+    fn unary_minus() {
+        assert_eq!(parse("-a").unwrap(), not(key("a")));
+        assert_eq!(parse("--a").unwrap(), not(not(key("a"))));
+        assert_eq!(parse("a * -b").unwrap(), and(key("a"), not(key("b"))));
+        assert_eq!(parse("-a * b").unwrap(), and(not(key("a")), key("b")));
+        assert_eq!(parse("-(a + b)").unwrap(), not(or(key("a"), key("b"))));
+    }
+
+    #[test]
+    fn quoting_and_escaping() {
+        assert_eq!(parse(r#""x86-64""#).unwrap(), key("x86-64"));
+        assert_eq!(parse(r#""error 500""#).unwrap(), key("error 500"));
+        assert_eq!(parse(r"x86\-64").unwrap(), key("x86-64"));
+        assert_eq!(parse(r#""say \"hi\"""#).unwrap(), key(r#"say "hi""#));
+        assert_eq!(parse(r#""a + b" + c"#).unwrap(), or(key("a + b"), key("c")));
+    }
+
+    #[test]
+    fn escapes_are_kept_for_patterns() {
+        let glob = Query::parse(r"a\*b", condition(MatchMode::Glob)).unwrap();
+        assert_eq!(glob, key(r"a\*b"));
+
+        let regex = Query::parse(r#""\d+""#, condition(MatchMode::Regex)).unwrap();
+        assert_eq!(regex, key(r"\d+"));
+    }
+
+    #[test]
+    fn regex_is_read_up_to_whitespace() {
+        let regex = |q: &str| Query::parse(q, condition(MatchMode::Regex)).unwrap();
+        assert_eq!(regex("kube.+s"), key("kube.+s"));
+        assert_eq!(regex("x86-64"), key("x86-64"));
+        assert_eq!(regex(r"err(or)? \d+"), and(key("err(or)?"), key(r"\d+")));
+        assert_eq!(regex("(a.* + b)"), or(key("a.*"), key("b")));
+        assert_eq!(regex("-a*"), not(key("a*")));
+    }
+
+    #[test]
+    fn trailing_operators_are_literal() {
+        assert_eq!(parse("c++").unwrap(), key("c++"));
+        assert_eq!(parse("C#-").unwrap(), key("C#-"));
+        assert_eq!(parse("c++ * java").unwrap(), and(key("c++"), key("java")));
+        assert_eq!(parse("(c++ + rust)").unwrap(), or(key("c++"), key("rust")));
+        assert_eq!(parse("c+-").unwrap(), key("c+-"));
+    }
+
+    #[test]
+    fn fields() {
+        assert_eq!(
+            parse("title:x86-64").unwrap(),
+            Query::Term(Term::Key {
+                field: Field::Title,
+                key: "x86-64".to_owned(),
+                fuzzy: false,
+            })
+        );
+        assert_eq!(
+            parse("~kubernets").unwrap(),
+            Query::Term(Term::Key {
+                field: Field::Any,
+                key: "kubernets".to_owned(),
+                fuzzy: true,
+            })
+        );
+    }
+
+    #[test]
+    fn meta_fields() {
+        assert_eq!(
+            parse("Error:timeout").unwrap(),
+            Query::Term(Term::Meta {
+                name: "error".to_owned(),
+                cmp: MetaCmp::parse("timeout"),
+                key: "Error:timeout".to_owned(),
+            })
+        );
+        assert_eq!(
+            Query::Term(parse("Error:timeout").unwrap().terms()[0].keyword()),
+            key("Error:timeout")
+        );
+        assert_eq!(parse("TODO:").unwrap(), key("TODO:"));
+        assert_eq!(parse("TODO: rust").unwrap(), and(key("TODO:"), key("rust")));
+        assert_eq!(parse("(TODO:)").unwrap(), key("TODO:"));
+    }
+
+    #[test]
+    fn errors() {
+        for query in [
+            "", "  ", "a +", "a + + b", "* a", "(a", "a)", "()", r#""open"#, "title:", "~", "-",
+        ] {
+            assert!(parse(query).is_err(), "`{query}` should be rejected");
+        }
+    }
+}
@@ -0,0 +1,145 @@
+#[allow(unused)]
+use {
+    super::{
+        error::MemoError,
+        memo::{MatchCondition, Matcher, MemoEntry, MemoSearch},
+        query::{Field, Query, Term},
+    },
+    chrono::Local,
+    clap::ValueEnum,
+    error_stack::{Report, Result, ResultExt},
+    jlogger_tracing::{
+        jdebug, jerror, jinfo, jtrace, jwarn, JloggerBuilder, LevelFilter, LogTimeFormat,
+    },
+    std::cmp::Ordering,
+};
+
+const TAG_WEIGHT: f64 = 5.0;
+const TITLE_WEIGHT: f64 = 3.0;
+const BODY_WEIGHT: f64 = 1.0;
+
+/// Notes created this many days ago get half of the recency bonus.
+const RECENCY_HALF_DAYS: f64 = 30.0;
+
+/// Order of the search result.
+#[derive(Debug, Clone, Copy, PartialEq, Default, ValueEnum)]
+pub enum SortKey {
+    /// Most relevant first
+    #[default]
+    Score,
+    /// Newest first
+    Created,
+    /// Alphabetical order of the title
+    Title,
+}
+
+/// Scores notes by how well they match the search keys.
+///
+/// Every hit of a key counts, tag hits weigh more than title hits which weigh more than body
+/// hits. Repeated hits add less and less, and newer notes get a small bonus.
+pub struct Scorer {
+    matchers: Vec<(Field, Matcher)>,
+}
+
+impl Scorer {
+    pub fn new(
+        query: Option<&Query>,
+        tag: Option<&str>,
+        condition: MatchCondition,
+    ) -> Result<Self, MemoError> {
+        let mut matchers = vec![];
+
+        if let Some(tag) = tag {
+            matchers.push((Field::Tag, Matcher::new(tag, condition)?));
+        }
+
+        if let Some(query) = query {
+            for term in query.terms() {
+                // A front matter field counts by its keyword, which is searched if no note
+                // has the field.
+                let term = term.keyword();
+                if let Term::Key { field, key, .. } = &term {
+                    if matches!(field, Field::Any | Field::Title | Field::Body | Field::Tag) {
+                        matchers.push((*field, Matcher::new(key, term.condition(condition))?));
+                    }
+                }
+            }
+        }
+
+        Ok(Self { matchers })
+    }
+
+    fn frequency(count: usize) -> f64 {
+        if count == 0 {
+            0.0
+        } else {
+            1.0 + (count as f64).ln()
+        }
+    }
+
+    pub fn score(&self, entry: &MemoEntry) -> f64 {
+        let mut score = 0.0;
+
+        for (field, matcher) in &self.matchers {
+            if matches!(field, Field::Any | Field::Tag) {
+                let hits = entry
+                    .tag_list()
+                    .iter()
+                    .map(|a| a.trim_matches('[').trim_matches(']'))
+                    .filter(|a| {
+                        if *field == Field::Tag {
+                            matcher.is_tag_match(a)
+                        } else {
+                            matcher.is_match(a)
+                        }
+                    })
+                    .count();
+                score += TAG_WEIGHT * hits as f64;
+            }
+
+            if matches!(field, Field::Any | Field::Title) {
+                score += TITLE_WEIGHT * Scorer::frequency(matcher.count(entry.title()));
+            }
+
+            if matches!(field, Field::Any | Field::Body) {
+                score += BODY_WEIGHT * Scorer::frequency(matcher.count(entry.body()));
+            }
+        }
+
+        if let Some(created) = entry.created() {
+            let days = (Local::now().naive_local() - created).num_hours().max(0) as f64 / 24.0;
+            score += RECENCY_HALF_DAYS / (RECENCY_HALF_DAYS + days);
+        }
+
+        score
+    }
+
+    /// Score the entries of `search` and sort them by `key`.
+    pub fn rank<'a>(&self, search: &MemoSearch<'a>, key: SortKey) -> Vec<(&'a MemoEntry, f64)> {
+        let mut ranked: Vec<(&MemoEntry, f64)> = search
+            .entries()
+            .into_iter()
+            .map(|e| (e, self.score(e)))
+            .collect();
+
+        let by_created = |a: &MemoEntry, b: &MemoEntry| b.created().cmp(&a.created());
+
+        match key {
+            SortKey::Score => ranked.sort_by(|(a, sa), (b, sb)| {
+                sb.partial_cmp(sa)
+                    .unwrap_or(Ordering::Equal)
+                    .then_with(|| by_created(a, b))
+            }),
+            SortKey::Created => ranked.sort_by(|(a, _), (b, _)| by_created(a, b)),
+            SortKey::Title => ranked.sort_by(|(a, _), (b, _)| {
+                a.title()
+                    .to_lowercase()
+                    .cmp(&b.title().to_lowercase())
+                    .then_with(|| by_created(a, b))
+            }),
+        }
+
+        jdebug!("ranked: {:?}", ranked);
+        ranked
+    }
+}
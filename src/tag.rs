@@ -0,0 +1,334 @@
+#[allow(unused)]
+use {
+    super::{
+        error::MemoError,
+        front::FrontMatter,
+        memo::{is_tag_name, normalize, tag_regex, MemoEntry},
+        tty::Tty,
+    },
+    chrono::NaiveDateTime,
+    error_stack::{Report, Result, ResultExt},
+    jlogger_tracing::{
+        jdebug, jerror, jinfo, jtrace, jwarn, JloggerBuilder, LevelFilter, LogTimeFormat,
+    },
+    std::{
+        collections::{BTreeMap, BTreeSet},
+        fs,
+        io::Write,
+    },
+};
+
+/// Usage of a tag across the store.
+#[derive(Debug)]
+pub struct TagStat {
+    /// Tag name without "[]", the full path in a tree like "proj/web".
+    pub name: String,
+    /// Level in the tree, 0 for top level tags.
+    pub depth: usize,
+    /// Number of notes with the tag.
+    pub count: usize,
+    pub first: Option<NaiveDateTime>,
+    pub last: Option<NaiveDateTime>,
+}
+
+impl TagStat {
+    /// Last part of a hierarchical name, e.g. "web" of "proj/web".
+    pub fn leaf(&self) -> &str {
+        self.name.rsplit('/').next().unwrap_or(&self.name)
+    }
+
+    /// Aggregate tags of `entries` in name order.
+    ///
+    /// With `tree`, a tag like "proj/web" also counts for "proj", and the result is in the
+    /// order of a tree walk, parents first.
+    pub fn collect(entries: &[&MemoEntry], tree: bool) -> Vec<TagStat> {
+        let mut stats: BTreeMap<Vec<String>, TagStat> = BTreeMap::new();
+
+        for entry in entries {
+            let mut names: BTreeSet<Vec<String>> = BTreeSet::new();
+            for tag in entry.tag_list() {
+                let name = normalize(tag.trim_matches('[').trim_matches(']'));
+                let path: Vec<String> = if tree {
+                    name.split('/').map(|a| a.to_owned()).collect()
+                } else {
+                    vec![name.into_owned()]
+                };
+
+                for i in 1..=path.len() {
+                    names.insert(path[..i].to_vec());
+                }
+            }
+
+            let created = entry.created();
+            for path in names {
+                let stat = stats.entry(path.clone()).or_insert_with(|| TagStat {
+                    name: path.join("/"),
+                    depth: path.len() - 1,
+                    count: 0,
+                    first: None,
+                    last: None,
+                });
+
+                stat.count += 1;
+                if let Some(t) = created {
+                    stat.first = Some(stat.first.map_or(t, |f| f.min(t)));
+                    stat.last = Some(stat.last.map_or(t, |l| l.max(t)));
+                }
+            }
+        }
+
+        stats.into_values().collect()
+    }
+}
+
+/// Renames and removals of tags, applied to the title lines of notes.
+pub struct Retag {
+    /// Old tag name to the new one, or `None` to remove the tag.
+    map: BTreeMap<String, Option<String>>,
+}
+
+/// Content of a note before and after retagging.
+pub struct TagChange {
+    pub path: String,
+    pub old: String,
+    pub new: String,
+}
+
+impl Retag {
+    fn check(name: &str) -> Result<(), MemoError> {
+        if is_tag_name(name) {
+            Ok(())
+        } else {
+            Err(Report::new(MemoError::InvalidValue)).attach_printable(format!(
+                "Invalid tag `{name}`, use letters, digits, '_', '-', ':' and '/'."
+            ))
+        }
+    }
+
+    fn name(tag: &str) -> &str {
+        tag.trim().trim_start_matches('[').trim_end_matches(']')
+    }
+
+    /// Name of a tag to compare, in NFC.
+    fn key(tag: &str) -> String {
+        normalize(Retag::name(tag)).into_owned()
+    }
+
+    pub fn rename(old: &str, new: &str) -> Result<Self, MemoError> {
+        Retag::merge(&[old], new)
+    }
+
+    /// Replace every tag in `tags` with `into`.
+    pub fn merge<S: AsRef<str>>(tags: &[S], into: &str) -> Result<Self, MemoError> {
+        let into = Retag::key(into);
+        Retag::check(&into)?;
+
+        let mut map = BTreeMap::new();
+        for tag in tags {
+            let tag = Retag::key(tag.as_ref());
+            Retag::check(&tag)?;
+            if tag != into {
+                map.insert(tag, Some(into.clone()));
+            }
+        }
+
+        if map.is_empty() {
+            return Err(Report::new(MemoError::InvalidValue))
+                .attach_printable(format!("Nothing to change, all tags are already `{into}`."));
+        }
+
+        Ok(Self { map })
+    }
+
+    pub fn remove(tag: &str) -> Result<Self, MemoError> {
+        let tag = Retag::key(tag);
+        Retag::check(&tag)?;
+
+        Ok(Self {
+            map: BTreeMap::from([(tag, None)]),
+        })
+    }
+
+    /// New name of `tag`, `Some(None)` if it is removed and `None` if it does not change.
+    ///
+    /// A renamed tag takes its subtree with it, e.g. renaming "proj" also renames
+    /// "proj/web" to "project/web".
+    fn lookup(&self, tag: &str) -> Option<Option<String>> {
+        let tag = &*normalize(tag);
+        if let Some(new) = self.map.get(tag) {
+            return Some(new.clone());
+        }
+
+        let mut parent = tag;
+        while let Some((p, _)) = parent.rsplit_once('/') {
+            parent = p;
+            if let Some(Some(new)) = self.map.get(parent) {
+                return Some(Some(format!("{new}{}", &tag[parent.len()..])));
+            }
+        }
+
+        None
+    }
+
+    /// Rewrite the tags of a title line.
+    ///
+    /// A tag renamed to one the title already has is dropped, and so is the space next to a
+    /// removed tag.
+    pub fn title(&self, line: &str) -> String {
+        let mut seen: BTreeSet<String> = BTreeSet::new();
+        for m in tag_regex().find_iter(line) {
+            if self.lookup(Retag::name(m.as_str())).is_none() {
+                seen.insert(Retag::key(m.as_str()));
+            }
+        }
+
+        let mut out = String::with_capacity(line.len());
+        let mut rest = 0;
+        let mut skip_space = false;
+
+        for m in tag_regex().find_iter(line) {
+            let mut before = &line[rest..m.start()];
+            if skip_space {
+                before = before.strip_prefix(' ').unwrap_or(before);
+            }
+            out.push_str(before);
+            rest = m.end();
+            skip_space = false;
+
+            match self.lookup(Retag::name(m.as_str())) {
+                None => out.push_str(m.as_str()),
+                Some(Some(new)) if seen.insert(new.clone()) => out.push_str(&format!("[{new}]")),
+                Some(_) => {
+                    if out.ends_with(' ') || out.is_empty() {
+                        skip_space = true;
+                    }
+                }
+            }
+        }
+
+        let mut after = &line[rest..];
+        if skip_space {
+            after = after.strip_prefix(' ').unwrap_or(after);
+        }
+        out.push_str(after);
+
+        out
+    }
+
+    /// Rewrite the tags of a note, both in the front matter and in the title line.
+    ///
+    /// `None` is returned if the title would be empty.
+    fn content(&self, content: &str) -> Option<String> {
+        let content = FrontMatter::retag(content, |t| match self.lookup(Retag::name(t)) {
+            None => Some(t.to_owned()),
+            Some(new) => new,
+        });
+
+        let (front, rest) = match FrontMatter::parse(&content) {
+            Some((front, rest)) => (Some(front), rest),
+            None => (None, content.as_str()),
+        };
+        let head = &content[..content.len() - rest.len()];
+        let mut out = String::with_capacity(content.len());
+
+        // The title is the "title" field or the first line after the front matter.
+        if front.is_some_and(|f| f.get("title").is_some()) {
+            for line in head.split_inclusive('\n') {
+                if FrontMatter::is_field(line, "title") {
+                    out.push_str(&self.title(line));
+                } else {
+                    out.push_str(line);
+                }
+            }
+            out.push_str(rest);
+            return Some(out);
+        }
+
+        out.push_str(head);
+        let mut done = false;
+        for line in rest.split_inclusive('\n') {
+            let text = line.trim_end_matches(['\r', '\n']);
+            if done || text.is_empty() {
+                out.push_str(line);
+                continue;
+            }
+
+            let new = self.title(text);
+            if new.trim().is_empty() {
+                return None;
+            }
+
+            out.push_str(&new);
+            out.push_str(&line[text.len()..]);
+            done = true;
+        }
+
+        Some(out)
+    }
+
+    /// Notes in `entries` which change.
+    pub fn plan(&self, entries: &[&MemoEntry]) -> Result<Vec<TagChange>, MemoError> {
+        let mut changes = vec![];
+
+        for e in entries.iter().filter(|e| {
+            e.tag_list()
+                .iter()
+                .any(|t| self.lookup(Retag::name(t)).is_some())
+        }) {
+            let old = fs::read_to_string(e.full_path()).map_err(|err| {
+                Report::new(MemoError::IOError)
+                    .attach_printable(format!("Failed to read {}: {err}", e.full_path()))
+            })?;
+
+            let Some(new) = self.content(&old) else {
+                jwarn!("{} would have no title, skip it.", e.full_path());
+                continue;
+            };
+
+            if new != old {
+                changes.push(TagChange {
+                    path: e.full_path().to_owned(),
+                    old,
+                    new,
+                });
+            }
+        }
+
+        Ok(changes)
+    }
+}
+
+impl TagChange {
+    /// Write the new content of the note, the file is replaced at once so that it is never
+    /// left half written.
+    pub fn apply(&self) -> Result<(), MemoError> {
+        let content = fs::read_to_string(&self.path).map_err(|e| {
+            Report::new(MemoError::IOError)
+                .attach_printable(format!("Failed to read {}: {e}", self.path))
+        })?;
+
+        if content != self.old {
+            return Err(Report::new(MemoError::Unexpected))
+                .attach_printable(format!("{} is changed, skip it.", self.path));
+        }
+
+        let tmp = format!("{}.tmp", self.path);
+        let write = || -> std::io::Result<()> {
+            let mut f = fs::File::create(&tmp)?;
+            f.write_all(self.new.as_bytes())?;
+            f.sync_all()?;
+            fs::rename(&tmp, &self.path)
+        };
+
+        write().map_err(|e| {
+            let _ = fs::remove_file(&tmp);
+            Report::new(MemoError::IOError)
+                .attach_printable(format!("Failed to write {}: {e}", self.path))
+        })
+    }
+
+    /// Unified diff of the changed lines.
+    pub fn diff(&self, color: bool) -> String {
+        Tty::diff(&self.path, &self.path, &self.old, &self.new, color)
+    }
+}
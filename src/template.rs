@@ -0,0 +1,156 @@
+#[allow(unused)]
+use {
+    super::{error::MemoError, memo::KINDS},
+    chrono::Local,
+    error_stack::{Report, Result, ResultExt},
+    jlogger_tracing::{
+        jdebug, jerror, jinfo, jtrace, jwarn, JloggerBuilder, LevelFilter, LogTimeFormat,
+    },
+    std::{
+        collections::HashMap,
+        env, fs,
+        io::{self, BufRead, Write},
+        path::Path,
+    },
+};
+
+/// A named note template stored as `{root}/templates/NAME.txt`, `NAME.html` or `NAME.md`.
+///
+/// Placeholders like `{{date}}` are expanded when a note is created from the template, the
+/// value of an unknown placeholder is asked to the user.
+pub struct Template {
+    content: String,
+    kind: String,
+}
+
+impl Template {
+    fn dir(root: &str) -> String {
+        format!("{root}/templates")
+    }
+
+    /// Names of the templates in `root`.
+    pub fn list(root: &str) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(Template::dir(root))
+            .map(|dir| {
+                dir.flatten()
+                    .map(|e| e.file_name().to_string_lossy().into_owned())
+                    .filter(|n| Template::suffix(n).is_some())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        names.sort();
+        names
+    }
+
+    /// Suffix of a template file name if it is a type of notes.
+    fn suffix(name: &str) -> Option<&str> {
+        name.rsplit_once('.')
+            .map(|(_, s)| s)
+            .filter(|s| KINDS.contains(s))
+    }
+
+    /// Load the template `name`, the one of `kind` is preferred.
+    ///
+    /// `name` may have the suffix, e.g. "incident.html".
+    pub fn load(root: &str, name: &str, kind: &str) -> Result<Self, MemoError> {
+        let dir = Template::dir(root);
+        let candidates = if Template::suffix(name).is_some() {
+            vec![name.to_owned()]
+        } else {
+            let mut kinds = vec![kind];
+            kinds.extend(KINDS.iter().filter(|k| **k != kind));
+            kinds.iter().map(|k| format!("{name}.{k}")).collect()
+        };
+
+        for c in candidates {
+            let file = format!("{dir}/{c}");
+            if !Path::new(&file).is_file() {
+                continue;
+            }
+
+            let content = fs::read_to_string(&file).map_err(|e| {
+                Report::new(MemoError::IOError)
+                    .attach_printable(format!("Failed to read {file}: {e}"))
+            })?;
+
+            jdebug!("template: {file}");
+            return Ok(Self {
+                content,
+                kind: Template::suffix(&c).unwrap_or("txt").to_owned(),
+            });
+        }
+
+        Err(Report::new(MemoError::InvalidValue)).attach_printable(format!(
+            "Template `{name}` is not found in {dir}, available: {}",
+            Template::list(root).join(" ")
+        ))
+    }
+
+    pub fn kind(&self) -> &str {
+        &self.kind
+    }
+
+    /// Content of the template with the placeholders replaced.
+    ///
+    /// `{{date}}`, `{{time}}`, `{{user}}` and `{{cwd}}` are filled in, other placeholders are
+    /// asked once each on the terminal.
+    pub fn expand(&self) -> Result<String, MemoError> {
+        let now = Local::now();
+        let mut values: HashMap<String, String> = HashMap::new();
+        values.insert("date".to_owned(), now.format("%Y-%m-%d").to_string());
+        values.insert("time".to_owned(), now.format("%H:%M").to_string());
+        values.insert(
+            "user".to_owned(),
+            env::var("USER").unwrap_or_else(|_| env::var("LOGNAME").unwrap_or_default()),
+        );
+        values.insert(
+            "cwd".to_owned(),
+            env::current_dir()
+                .map(|d| d.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+        );
+
+        let mut out = String::with_capacity(self.content.len());
+        let mut rest = self.content.as_str();
+
+        while let Some(start) = rest.find("{{") {
+            let Some(len) = rest[start + 2..].find("}}") else {
+                break;
+            };
+
+            let raw = &rest[start..start + len + 4];
+            let name = raw[2..raw.len() - 2].trim();
+            out.push_str(&rest[..start]);
+            rest = &rest[start + len + 4..];
+
+            // Not a placeholder, e.g. "{{ a b }}" in a code snippet.
+            if name.is_empty() || name.contains(char::is_whitespace) {
+                out.push_str(raw);
+                continue;
+            }
+
+            if !values.contains_key(name) {
+                let value = Template::ask(name)?;
+                values.insert(name.to_owned(), value);
+            }
+
+            out.push_str(&values[name]);
+        }
+
+        out.push_str(rest);
+        Ok(out)
+    }
+
+    fn ask(name: &str) -> Result<String, MemoError> {
+        eprint!("{name}: ");
+        let _ = io::stderr().flush();
+
+        let mut line = String::new();
+        io::stdin().lock().read_line(&mut line).map_err(|e| {
+            Report::new(MemoError::IOError).attach_printable(format!("Failed to read {name}: {e}"))
+        })?;
+
+        Ok(line.trim_end_matches(['\r', '\n']).to_owned())
+    }
+}
@@ -0,0 +1,262 @@
+#[allow(unused)]
+use {
+    super::{
+        date::DateRange,
+        error::MemoError,
+        front::FrontMatter,
+        memo::{FileName, MemoEntry, MIN_ID_LEN, SHORT_ID_LEN},
+    },
+    chrono::{Local, NaiveDateTime},
+    error_stack::{Report, Result, ResultExt},
+    jlogger_tracing::{
+        jdebug, jerror, jinfo, jtrace, jwarn, JloggerBuilder, LevelFilter, LogTimeFormat,
+    },
+    std::{fs, path::Path},
+};
+
+/// A deleted note in the trash.
+pub struct Trashed {
+    /// Path of the note in the trash.
+    pub path: String,
+    /// File name of the note.
+    pub name: String,
+    pub id: String,
+    pub title: String,
+    /// Path of the note before it was deleted.
+    pub origin: String,
+    pub deleted: NaiveDateTime,
+    /// How it was deleted, "rm" or "emptied" in the editor.
+    pub reason: String,
+}
+
+impl Trashed {
+    pub fn short_id(&self) -> &str {
+        &self.id[..SHORT_ID_LEN.min(self.id.len())]
+    }
+}
+
+/// Deleted notes kept under `{root}/trash/`.
+///
+/// Notes deleted at the same time are put in a directory named after the time, like
+/// "trash/20260131103000/2026_01_02_03_04_05.txt", with the metadata in a front matter
+/// file next to each note.
+pub struct Trash;
+
+impl Trash {
+    fn dir(root: &str) -> String {
+        format!("{root}/trash")
+    }
+
+    fn file_name(file: &str) -> &str {
+        Path::new(file)
+            .file_name()
+            .and_then(|a| a.to_str())
+            .unwrap_or(file)
+    }
+
+    /// Path in the trash for the note `file` deleted at `now`.
+    fn slot(root: &str, file: &str, now: NaiveDateTime) -> Result<String, MemoError> {
+        let dir = format!("{}/{}", Trash::dir(root), now.format("%Y%m%d%H%M%S"));
+        fs::create_dir_all(&dir).map_err(|e| {
+            Report::new(MemoError::IOError).attach_printable(format!("Failed to create {dir}: {e}"))
+        })?;
+
+        let to = format!("{dir}/{}", Trash::file_name(file));
+        if Path::new(&to).exists() {
+            return Err(Report::new(MemoError::InvalidValue))
+                .attach_printable(format!("{to} already exists."));
+        }
+
+        Ok(to)
+    }
+
+    fn write_meta(to: &str, file: &str, now: NaiveDateTime, reason: &str) -> Result<(), MemoError> {
+        let meta = format!(
+            "---\nname: {}\npath: {file}\ndeleted: {}\nreason: {reason}\n---\n",
+            Trash::file_name(file),
+            now.format("%Y-%m-%dT%H:%M:%S")
+        );
+
+        fs::write(format!("{to}.meta"), meta).map_err(|e| {
+            Report::new(MemoError::IOError)
+                .attach_printable(format!("Failed to write metadata of {to}: {e}"))
+        })
+    }
+
+    /// Remove the metadata of the note at `to`, and its directory if it is empty.
+    fn remove_meta(to: &str) {
+        let _ = fs::remove_file(format!("{to}.meta"));
+        if let Some(dir) = Path::new(to).parent() {
+            // Only removed if no other note is left in it.
+            let _ = fs::remove_dir(dir);
+        }
+    }
+
+    /// Move the note `file` to the trash, returns the path in the trash.
+    ///
+    /// The metadata is written first, so that the note is never in the trash without it.
+    pub fn put(root: &str, file: &str, reason: &str) -> Result<String, MemoError> {
+        let now = Local::now().naive_local();
+        let to = Trash::slot(root, file, now)?;
+        Trash::write_meta(&to, file, now, reason)?;
+
+        fs::rename(file, &to).map_err(|e| {
+            Trash::remove_meta(&to);
+            Report::new(MemoError::IOError)
+                .attach_printable(format!("Failed to move {file} to {to}: {e}"))
+        })?;
+
+        jdebug!("{file} is moved to {to}.");
+        Ok(to)
+    }
+
+    /// Keep `content` of the note `file` which is already gone, like a note emptied in the
+    /// editor.
+    pub fn put_content(
+        root: &str,
+        file: &str,
+        content: &str,
+        reason: &str,
+    ) -> Result<String, MemoError> {
+        let now = Local::now().naive_local();
+        let to = Trash::slot(root, file, now)?;
+        Trash::write_meta(&to, file, now, reason)?;
+
+        fs::write(&to, content).map_err(|e| {
+            let _ = fs::remove_file(&to);
+            Trash::remove_meta(&to);
+            Report::new(MemoError::IOError).attach_printable(format!("Failed to write {to}: {e}"))
+        })?;
+
+        jdebug!("Content of {file} is kept in {to}.");
+        Ok(to)
+    }
+
+    fn load(meta: &Path) -> Option<Trashed> {
+        let content = fs::read_to_string(meta).ok()?;
+        let (front, _) = FrontMatter::parse(&content)?;
+        let field = |k: &str| front.get(k).and_then(|v| v.as_str()).map(|a| a.to_owned());
+
+        let name = field("name")?;
+        let path = meta.with_file_name(&name).to_string_lossy().into_owned();
+        if !Path::new(&path).is_file() {
+            return None;
+        }
+
+        Some(Trashed {
+            id: FileName::from_file_name(&name).ok()?.id(),
+            title: MemoEntry::load(&path)
+                .map(|e| e.title().to_owned())
+                .unwrap_or_default(),
+            origin: field("path").unwrap_or_default(),
+            deleted: FrontMatter::datetime(&field("deleted")?)?,
+            reason: field("reason").unwrap_or_default(),
+            path,
+            name,
+        })
+    }
+
+    /// Notes in the trash, the latest deleted first.
+    pub fn list(root: &str) -> Vec<Trashed> {
+        let mut list = vec![];
+        let dirs = fs::read_dir(Trash::dir(root))
+            .into_iter()
+            .flatten()
+            .flatten();
+
+        for dir in dirs.filter(|d| d.path().is_dir()) {
+            for f in fs::read_dir(dir.path()).into_iter().flatten().flatten() {
+                let meta = f.path();
+                if meta.extension().is_some_and(|a| a == "meta") {
+                    match Trash::load(&meta) {
+                        Some(t) => list.push(t),
+                        None => jwarn!("Skip broken trash entry {}.", meta.display()),
+                    }
+                }
+            }
+        }
+
+        list.sort_by(|a, b| b.deleted.cmp(&a.deleted).then(a.name.cmp(&b.name)));
+        list
+    }
+
+    /// The note `id` in the trash, the latest deleted one if it was deleted several times.
+    pub fn find(root: &str, id: &str) -> Result<Trashed, MemoError> {
+        let id = id.to_lowercase();
+        if id.len() < MIN_ID_LEN {
+            return Err(Report::new(MemoError::InvalidValue)).attach_printable(format!(
+                "ID `{id}` is too short, use at least {MIN_ID_LEN} characters."
+            ));
+        }
+
+        let found: Vec<Trashed> = Trash::list(root)
+            .into_iter()
+            .filter(|t| t.id.starts_with(&id))
+            .collect();
+
+        let Some(first) = found.first() else {
+            return Err(Report::new(MemoError::InvalidValue))
+                .attach_printable(format!("No note `{id}` in the trash."));
+        };
+
+        if found.iter().any(|t| t.id != first.id) {
+            let ids: Vec<&str> = found.iter().map(|t| t.short_id()).collect();
+            return Err(Report::new(MemoError::InvalidValue))
+                .attach_printable(format!("ID `{id}` is ambiguous: {}", ids.join(" ")));
+        }
+
+        Ok(found.into_iter().next().unwrap())
+    }
+
+    /// Remove the metadata of a note which is no longer in the trash.
+    fn forget(t: &Trashed) {
+        Trash::remove_meta(&t.path);
+    }
+
+    /// Move a note back to the notes, returns the restored path.
+    pub fn restore(root: &str, t: &Trashed) -> Result<String, MemoError> {
+        let to = format!("{root}/memo/{}", t.name);
+        if FileName::from_file_name(&t.name)?.taken(&format!("{root}/memo")) {
+            return Err(Report::new(MemoError::InvalidValue)).attach_printable(format!(
+                "A note of ID {} already exists, created at the same time as {}.",
+                t.short_id(),
+                t.name
+            ));
+        }
+
+        fs::rename(&t.path, &to).map_err(|e| {
+            Report::new(MemoError::IOError)
+                .attach_printable(format!("Failed to move {} to {to}: {e}", t.path))
+        })?;
+
+        Trash::forget(t);
+        Ok(to)
+    }
+
+    /// Delete notes in the trash permanently, only the ones deleted more than `older_than`
+    /// like "30d" ago if given. Returns the number of deleted notes.
+    pub fn empty(root: &str, older_than: Option<&str>) -> Result<usize, MemoError> {
+        let recent = older_than
+            .map(|a| {
+                DateRange::parse(&format!("last-{a}"))
+                    .attach_printable(format!("Invalid age `{a}`, use like 30d, 12h or 2w."))
+            })
+            .transpose()?;
+
+        let mut n = 0;
+        for t in Trash::list(root) {
+            if recent.is_some_and(|r| r.contains(t.deleted)) {
+                continue;
+            }
+
+            fs::remove_file(&t.path).map_err(|e| {
+                Report::new(MemoError::IOError)
+                    .attach_printable(format!("Failed to remove {}: {e}", t.path))
+            })?;
+            Trash::forget(&t);
+            n += 1;
+        }
+
+        Ok(n)
+    }
+}
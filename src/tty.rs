@@ -0,0 +1,503 @@
+#[allow(unused)]
+use {
+    super::{
+        history::{History, Revision},
+        html::Html,
+        memo::MemoEntry,
+        tag::TagStat,
+        trash::Trashed,
+    },
+    chrono::NaiveDateTime,
+    clap::ValueEnum,
+    std::{env, fmt::Write, fs},
+    unicode_width::{UnicodeWidthChar, UnicodeWidthStr},
+};
+
+/// Where and how search results are displayed.
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum OutputMode {
+    /// Html page opened with "$BROWSER"
+    Browser,
+    /// Coloured table printed to stdout
+    Tty,
+    /// Tab separated lines printed to stdout, for scripts
+    Plain,
+    /// JSON array printed to stdout
+    Json,
+    /// One JSON object per line printed to stdout
+    Ndjson,
+}
+
+const MAX_TITLE_WIDTH: usize = 60;
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const DIM: &str = "\x1b[2m";
+const YELLOW: &str = "\x1b[33m";
+const CYAN: &str = "\x1b[36m";
+const GREEN: &str = "\x1b[32m";
+
+pub struct Tty;
+
+impl Tty {
+    /// Colours are disabled by the `NO_COLOR` environment variable.
+    pub fn color_enabled() -> bool {
+        env::var_os("NO_COLOR").is_none()
+    }
+
+    /// Columns taken in the terminal, CJK characters take two.
+    fn width(s: &str) -> usize {
+        s.width()
+    }
+
+    fn truncate(s: &str, max: usize) -> String {
+        if Tty::width(s) <= max {
+            return s.to_owned();
+        }
+
+        let mut t = String::new();
+        let mut width = 0;
+        for c in s.chars() {
+            width += c.width().unwrap_or(0);
+            if width > max - 1 {
+                break;
+            }
+            t.push(c);
+        }
+
+        t.push('…');
+        t
+    }
+
+    fn cell(s: &str, width: usize, color: Option<&str>) -> String {
+        let pad = " ".repeat(width.saturating_sub(Tty::width(s)));
+        match color {
+            Some(c) => format!("{c}{s}{RESET}{pad}"),
+            None => format!("{s}{pad}"),
+        }
+    }
+
+    /// Render notes as a table with a header line.
+    pub fn table(entries: &[(&MemoEntry, f64)], show_score: bool, color: bool) -> String {
+        let mut rows = vec![];
+        for &(entry, score) in entries {
+            let mut row = vec![
+                entry.short_id().to_owned(),
+                entry.create_time(),
+                Tty::truncate(&Html::clear_html_tags(entry.title()), MAX_TITLE_WIDTH),
+                entry.tags(),
+            ];
+
+            if show_score {
+                row.push(format!("{score:.2}"));
+            }
+
+            rows.push(row);
+        }
+
+        let mut header = vec!["ID", "CREATED", "TITLE", "TAGS"];
+        if show_score {
+            header.push("SCORE");
+        }
+
+        let colors = [Some(YELLOW), Some(DIM), None, Some(CYAN), Some(GREEN)];
+        Tty::render(&header, rows, &colors, color)
+    }
+
+    /// Lay out `rows` in columns under `header`.
+    fn render(
+        header: &[&str],
+        rows: Vec<Vec<String>>,
+        colors: &[Option<&str>],
+        color: bool,
+    ) -> String {
+        let mut widths: Vec<usize> = header.iter().map(|h| Tty::width(h)).collect();
+        for row in &rows {
+            for (i, c) in row.iter().enumerate() {
+                widths[i] = widths[i].max(Tty::width(c));
+            }
+        }
+
+        let mut table = String::new();
+
+        let line: Vec<String> = header
+            .iter()
+            .enumerate()
+            .map(|(i, h)| Tty::cell(h, widths[i], if color { Some(BOLD) } else { None }))
+            .collect();
+        let _ = writeln!(table, "{}", line.join("  ").trim_end());
+
+        for row in rows {
+            let line: Vec<String> = row
+                .iter()
+                .enumerate()
+                .map(|(i, c)| Tty::cell(c, widths[i], if color { colors[i] } else { None }))
+                .collect();
+            let _ = writeln!(table, "{}", line.join("  ").trim_end());
+        }
+
+        table
+    }
+
+    fn date(t: Option<NaiveDateTime>) -> String {
+        t.map(|t| t.format("%Y/%m/%d").to_string())
+            .unwrap_or("-".to_owned())
+    }
+
+    /// Render the current content of a note and its revisions, the latest first.
+    pub fn log(entry: &MemoEntry, revs: &[Revision], color: bool) -> String {
+        let mut rows = vec![vec![
+            "current".to_owned(),
+            Tty::datetime(History::modified(entry.full_path())),
+            fs::read_to_string(entry.full_path())
+                .map(|c| c.lines().count())
+                .unwrap_or(0)
+                .to_string(),
+            Tty::truncate(&Html::clear_html_tags(entry.title()), MAX_TITLE_WIDTH),
+        ]];
+
+        for r in revs.iter().rev() {
+            rows.push(vec![
+                r.number.to_string(),
+                Tty::datetime(Some(r.time)),
+                r.lines().to_string(),
+                Tty::truncate(&Html::clear_html_tags(&r.title()), MAX_TITLE_WIDTH),
+            ]);
+        }
+
+        let colors = [Some(YELLOW), Some(DIM), Some(GREEN), None];
+        Tty::render(&["REV", "WRITTEN", "LINES", "TITLE"], rows, &colors, color)
+    }
+
+    /// Render revisions as tab separated lines of number, time, lines, title and path, the
+    /// latest first.
+    pub fn plain_log(revs: &[Revision]) -> String {
+        let mut plain = String::new();
+
+        for r in revs.iter().rev() {
+            let _ = writeln!(
+                plain,
+                "{}\t{}\t{}\t{}\t{}",
+                r.number,
+                Tty::datetime(Some(r.time)),
+                r.lines(),
+                Html::clear_html_tags(&r.title()),
+                r.path
+            );
+        }
+
+        plain
+    }
+
+    fn datetime(t: Option<NaiveDateTime>) -> String {
+        t.map(|t| t.format("%Y/%m/%d %H:%M:%S").to_string())
+            .unwrap_or("-".to_owned())
+    }
+
+    /// Lines of `old` and `new` marked ' ' if kept, '-' if removed and '+' if added, from
+    /// the longest common subsequence of the lines.
+    fn diff_lines<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<(char, &'a str)> {
+        let head = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+        let tail = old[head..]
+            .iter()
+            .rev()
+            .zip(new[head..].iter().rev())
+            .take_while(|(a, b)| a == b)
+            .count();
+        let (a, b) = (&old[head..old.len() - tail], &new[head..new.len() - tail]);
+
+        // lcs[i][j] is the length of the common subsequence of a[i..] and b[j..].
+        let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+        for i in (0..a.len()).rev() {
+            for j in (0..b.len()).rev() {
+                lcs[i][j] = if a[i] == b[j] {
+                    lcs[i + 1][j + 1] + 1
+                } else {
+                    lcs[i + 1][j].max(lcs[i][j + 1])
+                };
+            }
+        }
+
+        let mut lines: Vec<(char, &str)> = old[..head].iter().map(|l| (' ', *l)).collect();
+        let (mut i, mut j) = (0, 0);
+        while i < a.len() || j < b.len() {
+            if i < a.len() && j < b.len() && a[i] == b[j] {
+                lines.push((' ', a[i]));
+                i += 1;
+                j += 1;
+            } else if j == b.len() || (i < a.len() && lcs[i + 1][j] >= lcs[i][j + 1]) {
+                lines.push(('-', a[i]));
+                i += 1;
+            } else {
+                lines.push(('+', b[j]));
+                j += 1;
+            }
+        }
+        lines.extend(old[old.len() - tail..].iter().map(|l| (' ', *l)));
+        lines
+    }
+
+    /// Unified diff of `old` and `new` with 3 lines of context around each change, empty if
+    /// they are the same.
+    pub fn diff(old_name: &str, new_name: &str, old: &str, new: &str, color: bool) -> String {
+        const CONTEXT: usize = 3;
+
+        let (red, green, cyan, reset) = if color {
+            ("\x1b[31m", "\x1b[32m", CYAN, RESET)
+        } else {
+            ("", "", "", "")
+        };
+
+        let old: Vec<&str> = old.lines().collect();
+        let new: Vec<&str> = new.lines().collect();
+        let lines = Tty::diff_lines(&old, &new);
+
+        // Ranges of the lines shown in each hunk, close changes share a hunk.
+        let mut hunks: Vec<(usize, usize)> = vec![];
+        for (i, _) in lines.iter().enumerate().filter(|(_, (c, _))| *c != ' ') {
+            let from = i.saturating_sub(CONTEXT);
+            let to = (i + CONTEXT + 1).min(lines.len());
+            match hunks.last_mut() {
+                Some(last) if from <= last.1 => last.1 = to,
+                _ => hunks.push((from, to)),
+            }
+        }
+
+        if hunks.is_empty() {
+            return String::new();
+        }
+
+        let mut diff = format!("--- {old_name}\n+++ {new_name}\n");
+        // Line numbers in `old` and `new` of the first line of the next hunk.
+        let (mut old_line, mut new_line, mut shown) = (1, 1, 0);
+        for (from, to) in hunks {
+            for (c, _) in &lines[shown..from] {
+                old_line += usize::from(*c != '+');
+                new_line += usize::from(*c != '-');
+            }
+
+            let hunk = &lines[from..to];
+            let old_len = hunk.iter().filter(|(c, _)| *c != '+').count();
+            let new_len = hunk.iter().filter(|(c, _)| *c != '-').count();
+            // An empty side is numbered by the line before it.
+            let start = |line: usize, len: usize| if len == 0 { line - 1 } else { line };
+            diff.push_str(&format!(
+                "{cyan}@@ -{},{old_len} +{},{new_len} @@{reset}\n",
+                start(old_line, old_len),
+                start(new_line, new_len)
+            ));
+
+            for (c, l) in hunk {
+                match c {
+                    '-' => diff.push_str(&format!("{red}-{l}{reset}\n")),
+                    '+' => diff.push_str(&format!("{green}+{l}{reset}\n")),
+                    _ => diff.push_str(&format!(" {l}\n")),
+                }
+            }
+
+            old_line += old_len;
+            new_line += new_len;
+            shown = to;
+        }
+
+        diff
+    }
+
+    /// Render tag statistics as a table, names are indented by their level with `tree`.
+    pub fn tags(stats: &[TagStat], tree: bool, color: bool) -> String {
+        let rows = stats
+            .iter()
+            .map(|s| {
+                let name = if tree {
+                    format!("{}{}", "  ".repeat(s.depth), s.leaf())
+                } else {
+                    s.name.clone()
+                };
+
+                vec![
+                    name,
+                    s.count.to_string(),
+                    Tty::date(s.first),
+                    Tty::date(s.last),
+                ]
+            })
+            .collect();
+
+        let colors = [Some(CYAN), Some(GREEN), Some(DIM), Some(DIM)];
+        Tty::render(&["TAG", "NOTES", "FIRST", "LAST"], rows, &colors, color)
+    }
+
+    /// Render notes in the trash as a table.
+    pub fn trash(list: &[Trashed], color: bool) -> String {
+        let rows = list
+            .iter()
+            .map(|t| {
+                vec![
+                    t.short_id().to_owned(),
+                    t.deleted.format("%Y/%m/%d %H:%M:%S").to_string(),
+                    t.reason.clone(),
+                    Tty::truncate(&Html::clear_html_tags(&t.title), MAX_TITLE_WIDTH),
+                ]
+            })
+            .collect();
+
+        let colors = [Some(YELLOW), Some(DIM), Some(CYAN), None];
+        Tty::render(&["ID", "DELETED", "REASON", "TITLE"], rows, &colors, color)
+    }
+
+    /// Render notes in the trash as tab separated lines of id, deletion time, reason, title
+    /// and path in the trash.
+    pub fn plain_trash(list: &[Trashed]) -> String {
+        let mut plain = String::new();
+
+        for t in list {
+            let _ = writeln!(
+                plain,
+                "{}\t{}\t{}\t{}\t{}",
+                t.short_id(),
+                t.deleted.format("%Y/%m/%d %H:%M:%S"),
+                t.reason,
+                Html::clear_html_tags(&t.title),
+                t.path
+            );
+        }
+
+        plain
+    }
+
+    /// Render tag statistics as tab separated lines of name, count, first and last date.
+    pub fn plain_tags(stats: &[TagStat]) -> String {
+        let mut plain = String::new();
+
+        for s in stats {
+            let _ = writeln!(
+                plain,
+                "{}\t{}\t{}\t{}",
+                s.name,
+                s.count,
+                Tty::date(s.first),
+                Tty::date(s.last)
+            );
+        }
+
+        plain
+    }
+
+    /// Render notes as tab separated lines of id, creation time, title, tags, score if
+    /// requested and path.
+    pub fn plain(entries: &[(&MemoEntry, f64)], show_score: bool) -> String {
+        let mut plain = String::new();
+
+        for &(entry, score) in entries {
+            let _ = write!(
+                plain,
+                "{}\t{}\t{}\t{}",
+                entry.short_id(),
+                entry.create_time(),
+                Html::clear_html_tags(entry.title()),
+                entry.tags()
+            );
+
+            if show_score {
+                let _ = write!(plain, "\t{score:.2}");
+            }
+
+            let _ = writeln!(plain, "\t{}", entry.full_path());
+        }
+
+        plain
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(n: usize) -> String {
+        (1..=n).map(|i| format!("{i}\n")).collect()
+    }
+
+    fn replace(text: &str, line: &str, with: &str) -> String {
+        text.lines()
+            .map(|l| format!("{}\n", if l == line { with } else { l }))
+            .collect()
+    }
+
+    fn headers(diff: &str) -> Vec<&str> {
+        diff.lines().filter(|l| l.starts_with("@@")).collect()
+    }
+
+    #[test]
+    fn lines_from_common_subsequence() {
+        assert_eq!(
+            Tty::diff_lines(&["a", "b", "c"], &["a", "c", "d"]),
+            vec![(' ', "a"), ('-', "b"), (' ', "c"), ('+', "d")]
+        );
+        assert_eq!(
+            Tty::diff_lines(&["a", "b"], &["b", "a"]),
+            vec![('-', "a"), (' ', "b"), ('+', "a")]
+        );
+    }
+
+    #[test]
+    fn no_change() {
+        assert_eq!(Tty::diff("a", "b", "x\ny\n", "x\ny\n", false), "");
+    }
+
+    #[test]
+    fn context_around_change() {
+        let old = lines(10);
+        let new = replace(&old, "5", "five");
+        assert_eq!(
+            Tty::diff("a", "b", &old, &new, false),
+            "--- a\n+++ b\n@@ -2,7 +2,7 @@\n 2\n 3\n 4\n-5\n+five\n 6\n 7\n 8\n"
+        );
+    }
+
+    #[test]
+    fn context_at_boundaries() {
+        let old = lines(5);
+        assert_eq!(
+            Tty::diff("a", "b", &old, &format!("{old}6\n"), false),
+            "--- a\n+++ b\n@@ -3,3 +3,4 @@\n 3\n 4\n 5\n+6\n"
+        );
+        assert_eq!(
+            Tty::diff("a", "b", &old, &format!("0\n{old}"), false),
+            "--- a\n+++ b\n@@ -1,3 +1,4 @@\n+0\n 1\n 2\n 3\n"
+        );
+    }
+
+    #[test]
+    fn empty_side() {
+        assert_eq!(
+            Tty::diff("a", "b", "", "x\ny\n", false),
+            "--- a\n+++ b\n@@ -0,0 +1,2 @@\n+x\n+y\n"
+        );
+        assert_eq!(
+            Tty::diff("a", "b", "x\ny\n", "", false),
+            "--- a\n+++ b\n@@ -1,2 +0,0 @@\n-x\n-y\n"
+        );
+    }
+
+    #[test]
+    fn hunks() {
+        // Changes with 6 lines between share a hunk, with 7 lines they do not.
+        let old = lines(10);
+        let new = replace(&replace(&old, "2", "two"), "8", "eight");
+        let diff = Tty::diff("a", "b", &old, &new, false);
+        assert_eq!(headers(&diff), vec!["@@ -1,10 +1,10 @@"]);
+
+        let old = lines(12);
+        let new = replace(&replace(&old, "2", "two"), "10", "ten");
+        let diff = Tty::diff("a", "b", &old, &new, false);
+        assert_eq!(headers(&diff), vec!["@@ -1,5 +1,5 @@", "@@ -7,6 +7,6 @@"]);
+    }
+
+    #[test]
+    fn hunks_after_inserted_lines() {
+        let old = lines(20);
+        let new = replace(&replace(&old, "2", "2\n2a\n2b"), "18", "eighteen");
+        let diff = Tty::diff("a", "b", &old, &new, false);
+        assert_eq!(headers(&diff), vec!["@@ -1,5 +1,7 @@", "@@ -15,6 +17,6 @@"]);
+    }
+}